@@ -1,9 +1,10 @@
 
 use crate::{
     point::Point,
-    actor::ActorId,
+    actor::{ActorId, CompanionCommand},
     world::{ViewportMode},
     item::ItemId,
+    equipment::EquipmentSlot,
     InputMode,
     message::Message
 };
@@ -15,6 +16,21 @@ pub enum Action {
     PickUp { actor_id: ActorId, items: Vec<ItemId> },
     UseItem { item_id: ItemId, target: ActorId },
     DropItem { item_id: ItemId },
+    OpenDoor { actor_id: ActorId, pos: Point },
+    CloseDoor { actor_id: ActorId, pos: Point },
+    OpenContainer { container_id: ItemId },
+    Equip { actor_id: ActorId, item_id: ItemId },
+    Unequip { actor_id: ActorId, slot: EquipmentSlot },
+    Buy { actor_id: ActorId, shopkeeper_id: ActorId, item_id: ItemId },
+    Sell { actor_id: ActorId, shopkeeper_id: ActorId, item_id: ItemId },
+    DialogueChoice { actor_id: ActorId, choice: usize },
+    Talk { actor_id: ActorId, partner_id: ActorId },
+    Attack { attacker_id: ActorId, defender_id: ActorId },
+    Zap { actor_id: ActorId, item_id: ItemId, target: Point },
+    Craft { actor_id: ActorId, recipe_id: String },
+    Search { actor_id: ActorId },
+    Disarm { actor_id: ActorId, pos: Point },
+    CommandCompanion { companion_id: ActorId, command: CompanionCommand },
     RunAI { actor_id: ActorId },
     DisplayMessage { msg: Message },
     Ouch,
@@ -31,6 +47,10 @@ pub enum GuiAction {
     HideShowInventory,
     HideShowHelp,
     HideShowStatus,
+    HideShowEquipment,
+    HideShowJournal,
+    HideShowCrafting,
+    HideShowCharacter,
     HideShowFOV,
     HideShowMessages,
     SwitchMode(InputMode)
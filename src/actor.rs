@@ -1,20 +1,46 @@
 use crate::{
     point::{Point, PointSet},
-    item::ItemId,
+    item::{ItemId, Weight},
     idmap::{Id, IdMap},
-    skill::{Skill, SkillKind, SkillDuration}
+    skill::{Skill, SkillKind, SkillDuration},
+    equipment::Equipment,
+    trade::Shop,
+    schedule::{Schedule, HOURS_PER_DAY, TURNS_PER_HOUR},
+    faction::Faction,
+    experience::{self, Experience}
 };
 
+use std::collections::HashMap;
+
 #[derive(Debug, Clone)]
 pub struct Actor {
     pub kind: ActorKind,
     pub pos: Point,
     pub health: Attribute,
+    pub strength: Attribute,
+    /// decreases with every turn and is restored by eating
+    pub nutrition: Attribute,
+    /// decreases with every turn in deep water
+    pub breath: Attribute,
+    /// number of turns the actor is held in place, e.g. by a snare
+    pub held: u16,
     pub coins: u16,
     pub ai: ActorAI,
     pub inventory: Inventory,
+    pub equipment: Equipment,
     pub visited: PointSet,
-    pub skills: Vec<Skill>
+    pub skills: Vec<Skill>,
+    pub experience: Experience,
+    /// how often each skill has been used
+    pub practice: HashMap<SkillKind, u16>,
+    pub shop: Option<Shop>,
+    /// position the actor returns to, e.g. by `ActorAI::ReturnHome`
+    pub home: Point,
+    pub faction: Option<Faction>,
+    /// a tamed animal follows its master
+    pub master: Option<ActorId>,
+    /// name of the dialogue that is used when talking to the actor
+    pub dialogue: Option<String>
 }
 
 pub type ActorId = Id<Actor>;
@@ -47,7 +73,7 @@ impl From<u16> for Attribute {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ActorKind {
     Player,
     Cat,
@@ -56,28 +82,198 @@ pub enum ActorKind {
     Shopkeeper
 }
 
+impl ActorKind {
+    /// Return the actor kind with the given name, as used in data files.
+    pub fn from_name(name: &str) -> Option<ActorKind> {
+        match name {
+            "player" => Some(ActorKind::Player),
+            "cat" => Some(ActorKind::Cat),
+            "dog" => Some(ActorKind::Dog),
+            "townsfolk" => Some(ActorKind::Townsfolk),
+            "shopkeeper" => Some(ActorKind::Shopkeeper),
+            _ => None
+        }
+    }
+
+    /// Faction which the actors of this kind belong to by default.
+    pub fn default_faction(&self) -> Option<Faction> {
+        match self {
+            ActorKind::Player => None,
+            ActorKind::Cat | ActorKind::Dog => Some(Faction::Wildlife),
+            ActorKind::Townsfolk | ActorKind::Shopkeeper => Some(Faction::Villagers)
+        }
+    }
+
+    /// Skills that the actors of this kind are born with.
+    pub fn innate_skills(&self) -> Vec<SkillKind> {
+        match self {
+            ActorKind::Dog => vec![SkillKind::Swim],
+            _ => vec![]
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            ActorKind::Player => format!("player"),
+            ActorKind::Cat => format!("a cat"),
+            ActorKind::Dog => format!("a dog"),
+            ActorKind::Townsfolk => format!("a villager"),
+            ActorKind::Shopkeeper => format!("a shopkeeper"),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum ActorAI {
     DoNothing,
     WanderAround,
+    /// stay close to the target, or go to where it was seen last
+    Follow { target: ActorId, last_seen: Option<Point> },
+    /// run away from the threat as long as it can be seen
+    Flee { threat: ActorId },
+    /// walk along the waypoints, over and over again
+    Patrol { waypoints: Vec<Point>, next: usize },
+    /// stay at the post and attack the player if the player is an
+    /// enemy and comes within the radius of the post
+    Guard { post: Point, radius: i32 },
+    /// go back to the home position and stay there
+    ReturnHome,
+    /// approach and attack the target
+    Hunt { target: ActorId, last_seen: Option<Point> },
+    /// go about the daily activities
+    FollowSchedule { schedule: Schedule },
+}
+
+/// Order that the player can give to a companion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompanionCommand {
+    Stay,
+    Follow
 }
 
 pub type Inventory = Vec<ItemId>;
 
+/// How much the carried load slows an actor down.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Encumbrance {
+    Unburdened,
+    Burdened,
+    Strained
+}
+
+impl Encumbrance {
+    /// Derive the encumbrance from the carried `load` and the
+    /// actor's `capacity`.
+    pub fn from_load(load: Weight, capacity: Weight) -> Self {
+        if 2 * load <= capacity {
+            Encumbrance::Unburdened
+        } else if 4 * load <= 3 * capacity {
+            Encumbrance::Burdened
+        } else {
+            Encumbrance::Strained
+        }
+    }
+
+    /// Number of extra turns that each move takes.
+    pub fn extra_turns(&self) -> usize {
+        match self {
+            Encumbrance::Unburdened => 0,
+            Encumbrance::Burdened => 1,
+            Encumbrance::Strained => 2
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Encumbrance::Unburdened => "unburdened",
+            Encumbrance::Burdened => "burdened",
+            Encumbrance::Strained => "strained"
+        }
+    }
+}
+
+/// Nutrition of a well-fed actor, which lasts for two days.
+pub const MAX_NUTRITION: u16 = 2 * (HOURS_PER_DAY * TURNS_PER_HOUR) as u16;
+
+/// Number of turns an actor can hold the breath in deep water.
+pub const MAX_BREATH: u16 = 10;
+
+/// How hungry an actor is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Hunger {
+    Satiated,
+    NotHungry,
+    Hungry,
+    Weak,
+    Starving
+}
+
+impl Hunger {
+    /// Derive the hunger from the actor's `nutrition`.
+    pub fn from_nutrition(nutrition: &Attribute) -> Self {
+        let (value, max) = (nutrition.value as u32, nutrition.max as u32);
+        if 4 * value >= 3 * max {
+            Hunger::Satiated
+        } else if 4 * value >= max {
+            Hunger::NotHungry
+        } else if 10 * value > max {
+            Hunger::Hungry
+        } else if value > 0 {
+            Hunger::Weak
+        } else {
+            Hunger::Starving
+        }
+    }
+
+    /// Penalty on the attack value.
+    pub fn attack_penalty(&self) -> i32 {
+        match self {
+            Hunger::Weak => 1,
+            Hunger::Starving => 2,
+            _ => 0
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Hunger::Satiated => "satiated",
+            Hunger::NotHungry => "not hungry",
+            Hunger::Hungry => "hungry",
+            Hunger::Weak => "weak with hunger",
+            Hunger::Starving => "starving"
+        }
+    }
+}
+
 impl Actor {
     pub fn new<P, A>(kind: ActorKind, pos: P, health: A) -> Self
     where P: Into<Point>, A: Into<Attribute>
     {
+        let pos = pos.into();
+        let faction = kind.default_faction();
+        let skills = kind.innate_skills().into_iter().map(Skill::new).collect();
         Self {
             kind,
-            pos: pos.into(),
+            pos,
+            home: pos,
+            faction,
+            master: None,
             ai: ActorAI::WanderAround,
             health: health.into(),
+            strength: Attribute::from(10),
+            nutrition: Attribute::from(MAX_NUTRITION),
+            breath: Attribute::from(MAX_BREATH),
+            held: 0,
             coins: 0,
             inventory: Vec::new(),
+            equipment: Equipment::default(),
             visited: PointSet::new(),
-            skills: Vec::new()
+            skills,
+            experience: Experience::default(),
+            practice: HashMap::new(),
+            shop: None,
+            dialogue: None
         }
     }
 
@@ -85,7 +281,33 @@ impl Actor {
         self.ai = ai;
         self
     }
+
+    pub fn with_home<P: Into<Point>>(mut self, home: P) -> Self {
+        self.home = home.into();
+        self
+    }
+
+    pub fn with_faction(mut self, faction: Faction) -> Self {
+        self.faction = Some(faction);
+        self
+    }
+
+    pub fn with_shop(mut self, shop: Shop) -> Self {
+        self.shop = Some(shop);
+        self
+    }
+
+    pub fn with_dialogue(mut self, dialogue: &str) -> Self {
+        self.dialogue = Some(dialogue.into());
+        self
+    }
     
+    /// Maximum weight that the actor can carry, derived from the
+    /// actor's strength.
+    pub fn capacity(&self) -> Weight {
+        6 * self.strength.value as Weight
+    }
+
     pub fn is_npc(&self) -> bool {
         match self.kind {
             ActorKind::Player => false,
@@ -93,19 +315,42 @@ impl Actor {
         }
     }
 
+    /// Animals can be tamed by feeding them.
+    pub fn is_animal(&self) -> bool {
+        matches!(self.kind, ActorKind::Cat | ActorKind::Dog)
+    }
+
+    /// Return true if the actor is a companion that follows its master.
+    pub fn is_following(&self) -> bool {
+        self.master.is_some() && matches!(self.ai, ActorAI::Follow { .. })
+    }
+
+    /// Natural swimmers never run out of breath.
+    pub fn is_natural_swimmer(&self) -> bool {
+        self.kind.innate_skills().contains(&SkillKind::Swim)
+    }
+
+    pub fn hunger(&self) -> Hunger {
+        Hunger::from_nutrition(&self.nutrition)
+    }
+
     pub fn has_skill(&self, kind: &SkillKind) -> bool {
         self.skills.iter().
             any(|skill| skill.kind == *kind)
     }
 
+    /// Rank of the skill, which grows with practice.
+    pub fn skill_rank(&self, kind: &SkillKind) -> u16 {
+        experience::rank(self.practice.get(kind).cloned().unwrap_or(0))
+    }
+
+    /// Chance that using the skill fails.
+    pub fn failure_chance(&self, kind: &SkillKind) -> f32 {
+        experience::failure_chance(self.skill_rank(kind))
+    }
+
     pub fn description(&self) -> String {
-        match self.kind {
-            ActorKind::Player => format!("player"),
-            ActorKind::Cat => format!("a cat"),
-            ActorKind::Dog => format!("a dog"),
-            ActorKind::Townsfolk => format!("a villager"),
-            ActorKind::Shopkeeper => format!("a shopkeeper"),
-        }
+        self.kind.description()
     }
 
     pub fn quip(&self) -> Option<String> {
@@ -0,0 +1,366 @@
+//! Behaviour of non-player actors.
+//!
+//! Each NPC has an `ActorAI`, which is run once per turn and which
+//! decides on the next action of the actor. Some behaviours keep
+//! their own state, e.g. the next waypoint of a patrol.
+//!
+//! An NPC only perceives other actors within its own field of view,
+//! as stored in `World::fov`.
+//!
+
+use crate::{
+    world::World,
+    actor::{ActorId, ActorAI},
+    action::Action,
+    point::Point,
+    path::find_path,
+    schedule::hour_of_day,
+    faction::Attitude,
+    terrain::TerrainAccess
+};
+
+use rand::Rng;
+
+/// Run the AI of the given actor and return the resulting action,
+/// if any.
+pub fn run_ai<R: Rng>(world: &mut World, actor_id: &ActorId, rng: &mut R) -> Option<Action> {
+    if !world.fov.contains_key(actor_id) {
+        world.update_fov(actor_id);
+    }
+
+    let actor = world.actors.get(actor_id)?;
+    let pos = actor.pos;
+    let home = actor.home;
+    let hostile = world.attitude_to_player(actor_id) == Attitude::Hostile;
+    let mut ai = actor.ai.clone();
+
+    // enemies attack the player on sight, unless they are busy
+    // with something more important
+    let player_id = world.player_id();
+    let busy = matches!(ai, ActorAI::DoNothing | ActorAI::Flee { .. } | ActorAI::Guard { .. }
+                        | ActorAI::Hunt { .. } | ActorAI::Follow { .. });
+    if hostile && !busy {
+        if let Some(player_pos) = perceive(world, actor_id, &player_id) {
+            return attack_or_approach(world, actor_id, pos, &player_id, player_pos);
+        }
+    }
+
+    let action = match &mut ai {
+        ActorAI::DoNothing => None,
+        ActorAI::WanderAround => {
+            if rng.gen::<f32>() > 0.3 {
+                wander(world, actor_id, pos, None, rng).map(|pos| move_to(world, actor_id, pos))
+            } else {
+                None
+            }
+        },
+        ActorAI::Follow { target, last_seen } => {
+            if let Some(target_pos) = perceive(world, actor_id, target) {
+                *last_seen = Some(target_pos);
+            }
+            match last_seen {
+                Some(goal) if pos.manhattan_distance(goal) > 1 => {
+                    step_towards(world, actor_id, pos, *goal).map(|pos| move_to(world, actor_id, pos))
+                },
+                _ => None
+            }
+        },
+        ActorAI::Flee { threat } => {
+            perceive(world, actor_id, threat)
+                .and_then(|threat_pos| step_away(world, actor_id, pos, threat_pos))
+                .map(|pos| move_to(world, actor_id, pos))
+        },
+        ActorAI::Patrol { waypoints, next } => {
+            if waypoints.is_empty() {
+                None
+            } else {
+                if pos == waypoints[*next] {
+                    *next = (*next + 1) % waypoints.len();
+                }
+                step_towards(world, actor_id, pos, waypoints[*next]).map(|pos| move_to(world, actor_id, pos))
+            }
+        },
+        ActorAI::Guard { post, radius } => {
+            let intruder = perceive(world, actor_id, &player_id)
+                .filter(|player_pos| hostile && player_pos.manhattan_distance(post) <= *radius);
+            match intruder {
+                Some(player_pos) => attack_or_approach(world, actor_id, pos, &player_id, player_pos),
+                None if pos != *post => step_towards(world, actor_id, pos, *post).map(|pos| move_to(world, actor_id, pos)),
+                None => None
+            }
+        },
+        ActorAI::ReturnHome => {
+            if pos != home {
+                step_towards(world, actor_id, pos, home).map(|pos| move_to(world, actor_id, pos))
+            } else {
+                None
+            }
+        },
+        ActorAI::Hunt { target, last_seen } => {
+            match perceive(world, actor_id, target) {
+                Some(target_pos) => {
+                    *last_seen = Some(target_pos);
+                    // only an enemy is attacked, anyone else is just followed
+                    if world.is_hostile_to(actor_id, target) {
+                        attack_or_approach(world, actor_id, pos, target, target_pos)
+                    } else if pos.manhattan_distance(&target_pos) > 1 {
+                        step_towards(world, actor_id, pos, target_pos).map(|pos| move_to(world, actor_id, pos))
+                    } else {
+                        None
+                    }
+                },
+                // search the place where the target was seen last
+                None => match *last_seen {
+                    Some(goal) if goal != pos => {
+                        step_towards(world, actor_id, pos, goal).map(|pos| move_to(world, actor_id, pos))
+                    },
+                    _ => {
+                        *last_seen = None;
+                        wander(world, actor_id, pos, None, rng).map(|pos| move_to(world, actor_id, pos))
+                    }
+                }
+            }
+        },
+        ActorAI::FollowSchedule { schedule } => {
+            match schedule.current(hour_of_day(world.time)) {
+                // on the way to the place of the current activity
+                Some(entry) if pos.manhattan_distance(&entry.place) > entry.activity.radius() => {
+                    step_towards(world, actor_id, pos, entry.place).map(|pos| move_to(world, actor_id, pos))
+                },
+                // walk around a bit, but stay close to the place
+                Some(entry) if rng.gen::<f32>() > 0.5 => {
+                    let area = (entry.place, entry.activity.radius());
+                    wander(world, actor_id, pos, Some(area), rng).map(|pos| move_to(world, actor_id, pos))
+                },
+                _ => None
+            }
+        }
+    };
+
+    if let Some(actor) = world.actors.get_mut(actor_id) {
+        actor.ai = ai;
+    }
+    action
+}
+
+/// Return the position of the target, if the actor can see it.
+pub fn perceive(world: &World, actor_id: &ActorId, target: &ActorId) -> Option<Point> {
+    let target_pos = world.actors.get(target)?.pos;
+    let fov = world.fov.get(actor_id)?;
+    if fov.contains(&target_pos) {
+        Some(target_pos)
+    } else {
+        None
+    }
+}
+
+/// Move the actor to the adjacent position. A closed door on the way
+/// is opened first.
+fn move_to(world: &World, actor_id: &ActorId, pos: Point) -> Action {
+    match world.terrain.get(&pos).unwrap_or_default().access() {
+        TerrainAccess::Openable => Action::OpenDoor { actor_id: *actor_id, pos },
+        _ => Action::Move { actor_id: *actor_id, pos }
+    }
+}
+
+/// Return true if the actor can pass the position, possibly after
+/// opening a door.
+fn is_passable(world: &World, actor_id: &ActorId, pos: &Point) -> bool {
+    world.can_enter(actor_id, pos)
+        || matches!(world.terrain.get(pos).unwrap_or_default().access(), TerrainAccess::Openable)
+}
+
+fn attack_or_approach(world: &World, actor_id: &ActorId, pos: Point, target: &ActorId, target_pos: Point)
+                      -> Option<Action> {
+    if pos.manhattan_distance(&target_pos) == 1 {
+        Some(Action::Attack { attacker_id: *actor_id, defender_id: *target })
+    } else {
+        step_towards(world, actor_id, pos, target_pos).map(|pos| move_to(world, actor_id, pos))
+    }
+}
+
+/// Return all adjacent positions that the actor could move to.
+fn free_neighbours(world: &World, actor_id: &ActorId, pos: Point) -> Vec<Point> {
+    pos.neighbours().iter()
+        .filter(|p| is_passable(world, actor_id, p) && !World::actor_blocking(p, &world.actors))
+        .cloned()
+        .collect()
+}
+
+/// Return a random adjacent position. If an area (center and
+/// radius) is given, the position must lie within the area.
+fn wander<R: Rng>(world: &World, actor_id: &ActorId, pos: Point, area: Option<(Point, i32)>, rng: &mut R) -> Option<Point> {
+    let candidates = free_neighbours(world, actor_id, pos).into_iter()
+        .filter(|p| area.map_or(true, |(center, radius)| p.manhattan_distance(&center) <= radius))
+        .collect::<Vec<Point>>();
+    if candidates.is_empty() {
+        None
+    } else {
+        Some(candidates[rng.gen_range(0..candidates.len())])
+    }
+}
+
+/// Return the next position on the shortest path to the goal. If
+/// the next position is occupied by another actor, the actor waits.
+fn step_towards(world: &World, actor_id: &ActorId, pos: Point, goal: Point) -> Option<Point> {
+    let path = find_path(pos, goal, |p| is_passable(world, actor_id, p))?;
+    let next = *path.first()?;
+    if World::actor_blocking(&next, &world.actors) {
+        None
+    } else {
+        Some(next)
+    }
+}
+
+/// Return the adjacent position that brings the actor farther away
+/// from the threat.
+fn step_away(world: &World, actor_id: &ActorId, pos: Point, threat: Point) -> Option<Point> {
+    let distance = pos.manhattan_distance(&threat);
+    free_neighbours(world, actor_id, pos).into_iter()
+        .filter(|p| p.manhattan_distance(&threat) > distance)
+        .max_by_key(|p| p.manhattan_distance(&threat))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actor::{Actor, ActorKind},
+        terrain::{Terrain, TerrainKind, DoorState},
+        faction::Faction
+    };
+
+    /// Set up a world with some grass around the player, as unmapped
+    /// tiles cannot be entered.
+    fn open_field() -> World {
+        let mut world = World::new();
+        let center = world.player_pos();
+        for x in -10..=10 {
+            for y in -10..=10 {
+                world.terrain.insert(center.offset(x, y), Terrain::from(TerrainKind::Grass));
+            }
+        }
+        world
+    }
+
+    /// Set up a world with an NPC next to the player. Ids are unique,
+    /// so the NPC's AI is derived from the player's id.
+    fn world_with_npc<F>(ai: F, offset: (i32, i32)) -> (World, ActorId)
+    where F: FnOnce(ActorId) -> ActorAI
+    {
+        let mut world = open_field();
+        let pos = world.player_pos() + Point::from(offset);
+        let ai = ai(world.player_id());
+        let npc_id = world.actors.add(Actor::new(ActorKind::Townsfolk, pos, 5).with_ai(ai));
+        world.update_fov(&npc_id);
+        (world, npc_id)
+    }
+
+    fn run(world: &mut World, npc_id: &ActorId) -> Option<Action> {
+        run_ai(world, npc_id, &mut rand::thread_rng())
+    }
+
+    #[test]
+    fn follow_the_player() {
+        let follow = |target| ActorAI::Follow { target, last_seen: None };
+        let (mut world, npc_id) = world_with_npc(follow, (3, 0));
+
+        let player_pos = world.player_pos();
+        let action = run(&mut world, &npc_id);
+        assert!(matches!(action, Some(Action::Move { pos, .. }) if pos == player_pos + Point::from((2, 0))));
+    }
+
+    #[test]
+    fn do_not_follow_what_cannot_be_seen() {
+        let follow = |target| ActorAI::Follow { target, last_seen: None };
+        let (mut world, npc_id) = world_with_npc(follow, (3, 0));
+        let wall = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(wall, Terrain::from(TerrainKind::Wall));
+        world.update_fov(&npc_id);
+
+        assert!(run(&mut world, &npc_id).is_none());
+    }
+
+    #[test]
+    fn flee_from_the_player() {
+        let (mut world, npc_id) = world_with_npc(|threat| ActorAI::Flee { threat }, (2, 0));
+
+        let player_pos = world.player_pos();
+        match run(&mut world, &npc_id) {
+            Some(Action::Move { pos, .. }) => assert!(pos.manhattan_distance(&player_pos) > 2),
+            action => panic!("unexpected action {:?}", action)
+        }
+    }
+
+    #[test]
+    fn patrol_advances_to_next_waypoint() {
+        let mut world = open_field();
+        let start = world.player_pos() + Point::from((0, 5));
+        let waypoints = vec![start, start + Point::from((3, 0))];
+        let ai = ActorAI::Patrol { waypoints, next: 0 };
+        let npc_id = world.actors.add(Actor::new(ActorKind::Townsfolk, start, 5).with_ai(ai));
+
+        let action = run(&mut world, &npc_id);
+        assert!(matches!(action, Some(Action::Move { pos, .. }) if pos == start + Point::from((1, 0))));
+        assert!(matches!(world.actors.get(&npc_id).unwrap().ai, ActorAI::Patrol { next: 1, .. }));
+    }
+
+    #[test]
+    fn hunter_attacks_adjacent_target() {
+        let hunt = |target| ActorAI::Hunt { target, last_seen: None };
+        let (mut world, npc_id) = world_with_npc(hunt, (1, 0));
+        let player_id = world.player_id();
+        world.actors.get_mut(&npc_id).unwrap().faction = Some(Faction::Bandits);
+
+        let action = run(&mut world, &npc_id);
+        assert!(matches!(action, Some(Action::Attack { defender_id, .. }) if defender_id == player_id));
+    }
+
+    #[test]
+    fn peaceful_hunter_only_follows() {
+        let hunt = |target| ActorAI::Hunt { target, last_seen: None };
+        let (mut world, npc_id) = world_with_npc(hunt, (1, 0));
+        assert!(run(&mut world, &npc_id).is_none());
+
+        let player_pos = world.player_pos();
+        world.actors.get_mut(&npc_id).unwrap().pos = player_pos + Point::from((3, 0));
+        world.update_fov(&npc_id);
+        let action = run(&mut world, &npc_id);
+        assert!(matches!(action, Some(Action::Move { pos, .. }) if pos == player_pos + Point::from((2, 0))));
+    }
+
+    #[test]
+    fn open_closed_door_on_the_way() {
+        let mut world = open_field();
+        let door = world.player_pos() + Point::from((0, 3));
+        let home = door + Point::from((0, 3));
+        for x in -3..=3 {
+            world.terrain.insert(door + Point::from((x, 0)), Terrain::from(TerrainKind::Wall));
+        }
+        world.terrain.insert(door, Terrain::from(TerrainKind::Door(DoorState::Closed)));
+        let start = door + Point::from((0, -1));
+        let mut npc = Actor::new(ActorKind::Townsfolk, start, 5).with_ai(ActorAI::ReturnHome);
+        npc.home = home;
+        let npc_id = world.actors.add(npc);
+
+        let action = run(&mut world, &npc_id);
+        assert!(matches!(action, Some(Action::OpenDoor { pos, .. }) if pos == door));
+        world.open_door(&npc_id, &door);
+        assert!(world.messages.iter().all(|message| message.text != "You open the door."));
+        let action = run(&mut world, &npc_id);
+        assert!(matches!(action, Some(Action::Move { pos, .. }) if pos == door));
+    }
+
+    #[test]
+    fn peaceful_guard_ignores_the_player() {
+        let mut world = World::new();
+        let post = world.player_pos() + Point::from((1, 0));
+        let ai = ActorAI::Guard { post, radius: 3 };
+        let npc_id = world.actors.add(Actor::new(ActorKind::Townsfolk, post, 5).with_ai(ai));
+        assert!(run(&mut world, &npc_id).is_none());
+
+        world.actors.get_mut(&npc_id).unwrap().faction = Some(Faction::Bandits);
+        assert!(matches!(run(&mut world, &npc_id), Some(Action::Attack { .. })));
+    }
+}
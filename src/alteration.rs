@@ -0,0 +1,116 @@
+//! Altering the terrain with tools.
+//!
+//! Some tools change the terrain next to the player: a pickaxe digs
+//! through walls, an axe cuts hedges, a torch burns thick grass and
+//! planks make a bridge over the water. The changed terrain is put
+//! into the world by `World::alter_terrain`, which also updates the
+//! lights and the fields of view.
+//!
+//! A cut hedge at the border of the map does not lead anywhere, as
+//! the unmapped terrain beyond cannot be entered.
+//!
+//! TODO: the game cannot be saved yet, so the altered terrain is
+//! lost when the game ends. Once there are saves, `World::terrain`
+//! must be stored as a whole rather than reloaded from the layer file.
+//!
+
+use crate::{
+    point::Point,
+    item::ItemKind,
+    terrain::{Terrain, TerrainKind, Orientation}
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alteration {
+    Dig,
+    Cut,
+    Burn,
+    Bridge
+}
+
+impl Alteration {
+    /// Return the alteration that the tool performs, if any.
+    pub fn of_tool(kind: &ItemKind) -> Option<Alteration> {
+        match kind {
+            ItemKind::Pickaxe => Some(Alteration::Dig),
+            ItemKind::Axe => Some(Alteration::Cut),
+            ItemKind::Torch => Some(Alteration::Burn),
+            ItemKind::Planks => Some(Alteration::Bridge),
+            _ => None
+        }
+    }
+
+    /// Return the terrain at `pos` after the alteration, or None if
+    /// the alteration does not apply to the terrain. The position
+    /// `from` is where the worker stands.
+    pub fn apply(&self, tile: &Terrain, from: &Point, pos: &Point) -> Option<Terrain> {
+        let kind = match (self, &tile.kind) {
+            (Alteration::Dig, TerrainKind::Wall) => TerrainKind::StoneFloor,
+            (Alteration::Cut, TerrainKind::Hedge) => TerrainKind::Grass,
+            // burnt grass leaves nothing but bare earth
+            (Alteration::Burn, TerrainKind::ThickGrass) => TerrainKind::Path,
+            (Alteration::Bridge, TerrainKind::Water) |
+            (Alteration::Bridge, TerrainKind::ShallowWater) => {
+                if pos.x != from.x {
+                    TerrainKind::Bridge(Orientation::Horizontal)
+                } else {
+                    TerrainKind::Bridge(Orientation::Vertical)
+                }
+            },
+            _ => return None
+        };
+        Some(Terrain::from(kind))
+    }
+
+    /// Return true if the tool is used up by the alteration. All
+    /// other tools wear out.
+    pub fn consumes_tool(&self) -> bool {
+        matches!(self, Alteration::Bridge)
+    }
+
+    /// Return true if the tool wears out by the alteration.
+    pub fn wears_tool(&self) -> bool {
+        matches!(self, Alteration::Dig | Alteration::Cut)
+    }
+
+    /// Message that tells the player what has been done.
+    pub fn message(&self) -> &'static str {
+        match self {
+            Alteration::Dig => "You dig through the wall.",
+            Alteration::Cut => "You cut down the hedge.",
+            Alteration::Burn => "The grass goes up in flames and burns down.",
+            Alteration::Bridge => "You build a bridge over the water."
+        }
+    }
+
+    /// Message if there is nothing around to alter.
+    pub fn nothing_to_do(&self) -> &'static str {
+        match self {
+            Alteration::Dig => "There is no wall to dig through around you.",
+            Alteration::Cut => "There is no hedge to cut around you.",
+            Alteration::Burn => "There is nothing to burn around you.",
+            Alteration::Bridge => "There is no water to bridge around you."
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tools_alter_matching_terrain() {
+        let from = Point::new(0, 0);
+        let east = Point::new(1, 0);
+        let wall = Terrain::from(TerrainKind::Wall);
+        let water = Terrain::from(TerrainKind::Water);
+
+        assert_eq!(Alteration::Dig.apply(&wall, &from, &east).unwrap().kind, TerrainKind::StoneFloor);
+        assert!(Alteration::Cut.apply(&wall, &from, &east).is_none());
+        assert_eq!(Alteration::Bridge.apply(&water, &from, &east).unwrap().kind,
+                   TerrainKind::Bridge(Orientation::Horizontal));
+        assert_eq!(Alteration::Bridge.apply(&water, &from, &Point::new(0, 1)).unwrap().kind,
+                   TerrainKind::Bridge(Orientation::Vertical));
+    }
+}
@@ -0,0 +1,191 @@
+//! Crafting items from other items.
+//!
+//! A Recipe turns some input items into one or more new items. The
+//! inputs are used up, while the tools are only needed. Some recipes
+//! can only be crafted next to a certain terrain feature, e.g. a
+//! fountain or an anvil.
+//!
+//! Recipes are read from text files, e.g.
+//!
+//! ```text
+//! recipe sword
+//! title Forge a sword
+//! input ore 3
+//! tool hammer
+//! feature anvil
+//! output sword
+//! ```
+//!
+//! Lines starting with `#` are comments. The quantity of inputs and
+//! outputs is optional and defaults to one. A file may contain more
+//! than one recipe.
+//!
+
+use crate::{
+    item::ItemKind,
+    terrain::TerrainFeature
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Recipe {
+    pub id: String,
+    pub title: String,
+    /// items that are used up, with their quantity
+    pub inputs: Vec<(ItemKind, u16)>,
+    /// items that are needed, but not used up
+    pub tools: Vec<ItemKind>,
+    /// terrain feature that must be next to the crafter
+    pub feature: Option<TerrainFeature>,
+    pub outputs: Vec<(ItemKind, u16)>
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    Io(std::io::Error),
+    Parse { line: usize, reason: String },
+    NoInputs { recipe: String },
+    NoOutputs { recipe: String }
+}
+
+impl From<std::io::Error> for RecipeError {
+    fn from(error: std::io::Error) -> Self {
+        RecipeError::Io(error)
+    }
+}
+
+/// Parse an item with an optional quantity, e.g. `ore 3`.
+fn parse_items(text: &str) -> Option<(ItemKind, u16)> {
+    let (name, quantity) = match text.split_once(' ') {
+        Some((name, quantity)) => (name, quantity.trim().parse().ok()?),
+        None => (text, 1)
+    };
+    if quantity == 0 {
+        return None;
+    }
+    ItemKind::from_name(name).map(|kind| (kind, quantity))
+}
+
+/// Return the feature with the given name, as used in data files.
+fn parse_feature(name: &str) -> Option<TerrainFeature> {
+    match name {
+        "fountain" => Some(TerrainFeature::Fountain),
+        "anvil" => Some(TerrainFeature::Anvil),
+        _ => None
+    }
+}
+
+impl Recipe {
+    /// Parse all recipes from the given text. The recipes are not
+    /// validated.
+    pub fn parse(text: &str) -> Result<Vec<Recipe>, RecipeError> {
+        let mut recipes: Vec<Recipe> = vec!();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |reason: &str| RecipeError::Parse { line: n + 1, reason: reason.into() };
+            let (keyword, rest) = match line.split_once(' ') {
+                Some((keyword, rest)) => (keyword, rest.trim()),
+                None => (line, "")
+            };
+
+            if keyword == "recipe" {
+                if rest.is_empty() {
+                    return Err(error("recipe without id"));
+                }
+                recipes.push(Recipe { id: rest.into(), title: rest.replace('_', " "), ..Default::default() });
+                continue;
+            }
+
+            let recipe = recipes.last_mut()
+                .ok_or_else(|| error("line outside of a recipe"))?;
+
+            match keyword {
+                "title" => recipe.title = rest.into(),
+                "input" => recipe.inputs.push(parse_items(rest).ok_or_else(|| error("unknown input"))?),
+                "output" => recipe.outputs.push(parse_items(rest).ok_or_else(|| error("unknown output"))?),
+                "tool" => recipe.tools.push(ItemKind::from_name(rest).ok_or_else(|| error("unknown tool"))?),
+                "feature" => recipe.feature = Some(parse_feature(rest).ok_or_else(|| error("unknown feature"))?),
+                _ => return Err(error("unknown keyword"))
+            }
+        }
+
+        Ok(recipes)
+    }
+
+    /// Check that the recipe has at least one input and one output.
+    pub fn validate(&self) -> Result<(), RecipeError> {
+        if self.inputs.is_empty() {
+            return Err(RecipeError::NoInputs { recipe: self.id.clone() });
+        }
+        if self.outputs.is_empty() {
+            return Err(RecipeError::NoOutputs { recipe: self.id.clone() });
+        }
+        Ok(())
+    }
+}
+
+/// Read all recipes from the given file and validate them.
+pub fn read_from_file<P>(path: P) -> Result<Vec<Recipe>, RecipeError>
+where P: AsRef<std::path::Path>
+{
+    let text = std::fs::read_to_string(path)?;
+    let recipes = Recipe::parse(&text)?;
+    for recipe in &recipes {
+        recipe.validate()?;
+    }
+    Ok(recipes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "
+# a sword needs some ore
+recipe sword
+title Forge a sword
+input ore 3
+tool hammer
+feature anvil
+output sword
+
+recipe healing_potion
+input empty_potion
+feature fountain
+output potion_of_healing
+";
+
+    #[test]
+    fn parse_recipes() {
+        let recipes = Recipe::parse(SAMPLE).unwrap();
+        assert_eq!(recipes.len(), 2);
+
+        let sword = &recipes[0];
+        assert_eq!(sword.title, "Forge a sword");
+        assert_eq!(sword.inputs, vec![(ItemKind::Ore, 3)]);
+        assert_eq!(sword.tools, vec![ItemKind::Hammer]);
+        assert_eq!(sword.feature, Some(TerrainFeature::Anvil));
+        assert_eq!(sword.outputs, vec![(ItemKind::Sword, 1)]);
+        assert_eq!(recipes[1].title, "healing potion");
+    }
+
+    #[test]
+    fn recipe_errors() {
+        assert!(matches!(Recipe::parse("input ore"), Err(RecipeError::Parse { line: 1, .. })));
+        assert!(matches!(Recipe::parse("recipe x\ninput dragon"), Err(RecipeError::Parse { line: 2, .. })));
+        let recipes = Recipe::parse("recipe x\ninput ore").unwrap();
+        assert!(matches!(recipes[0].validate(), Err(RecipeError::NoOutputs { .. })));
+    }
+
+    #[test]
+    fn bundled_recipes_are_valid() {
+        if let Err(error) = read_from_file("assets/recipes.txt") {
+            panic!("assets/recipes.txt: {:?}", error);
+        }
+    }
+}
@@ -3,6 +3,14 @@ use crate::{
     actor::{Actor, ActorId, ActorKind, ActorAI},
     terrain::{TerrainKind, DoorState, Orientation, TerrainFeature},
     item::*,
+    trade::Shop,
+    faction::Faction,
+    spell::Spell,
+    trap::{Trap, TrapKind},
+    schedule::{Schedule, Activity, TURNS_PER_HOUR},
+    dialogue,
+    quest,
+    craft,
     point::Point,
     terrain::read_from_file
 };
@@ -14,6 +22,9 @@ use rand::Rng;
 use maplit::hashmap;
 
 pub fn populate_world(world: &mut World) {
+    // the game starts in the morning, when the shop opens
+    world.time = 8 * TURNS_PER_HOUR;
+
     // read map from file
     let kind_map = hashmap! {
         '.' => TerrainKind::Grass,
@@ -24,60 +35,153 @@ pub fn populate_world(world: &mut World) {
         'W' => TerrainKind::Water,
         '#' => TerrainKind::Wall,
         '~' => TerrainKind::ShallowWater,
-        'd' => TerrainKind::Door(DoorState::Locked),
+        'd' => TerrainKind::Door(DoorState::Locked(1)),
+        'c' => TerrainKind::Door(DoorState::Closed),
         'D' => TerrainKind::Door(DoorState::Open),
         '+' => TerrainKind::Window,
         'B' => TerrainKind::Bridge(Orientation::Vertical),
         'b' => TerrainKind::Bridge(Orientation::Horizontal),
         'f' => TerrainKind::Grass,
+        'I' => TerrainKind::Wall,
+        'S' => TerrainKind::Wall,
+        '^' => TerrainKind::Grass,
+        '%' => TerrainKind::Grass,
+        'a' => TerrainKind::Path,
     };
 
     let feature_map = hashmap! {
         'f' => TerrainFeature::Fountain,
+        'I' => TerrainFeature::Ivy,
+        'S' => TerrainFeature::SecretDoor,
+        // the villagers protect their homes against the bandits
+        '^' => TerrainFeature::Trap(Trap::hidden(TrapKind::Pit).with_owner(Faction::Villagers)),
+        '%' => TerrainFeature::Trap(Trap::hidden(TrapKind::Snare).with_owner(Faction::Villagers)),
+        'a' => TerrainFeature::Trap(Trap::hidden(TrapKind::AlarmPlate).with_owner(Faction::Villagers)),
     };
     
     world.terrain = read_from_file("assets/sample.layer", &kind_map, &feature_map).unwrap();
 
+    // read dialogues
+    for name in &["townsfolk", "shopkeeper"] {
+        let path = format!("assets/dialogues/{}.dlg", name);
+        let dialogue = dialogue::read_from_file(&path).unwrap();
+        world.dialogues.insert(name.to_string(), dialogue);
+    }
+
+    // read quests
+    for quest in quest::read_from_file("assets/quests.txt").unwrap() {
+        world.quests.insert(quest.id.clone(), quest);
+    }
+
+    // read crafting recipes
+    world.recipes = craft::read_from_file("assets/recipes.txt").unwrap();
+
     let player_id = world.player_id();
     
     // add some items to player's inventory
-    let magical_wand = Item::new(ItemKind::Wand)
-        .with_owner(player_id);
-    let some_money = Item::new(ItemKind::Money(42))
+    let magical_wand = Item::new(ItemKind::Wand(Spell::Bolt))
         .with_owner(player_id);
     let healing_potion = Item::new(ItemKind::Potion(Potion::Healing))
+        .with_quantity(3)
         .with_owner(player_id);
     let swimming_potion = Item::new(ItemKind::Potion(Potion::Swimming))
         .with_owner(player_id);
     let vision_potion = Item::new(ItemKind::Potion(Potion::Vision))
         .with_owner(player_id);
-    let key = Item::new(ItemKind::Key)
+    // the key fits the locked doors of the house in the east
+    let key = Item::new(ItemKind::Key(1))
         .with_owner(player_id);
 
     let player = world.actors.get_mut(&player_id).unwrap();
     player.inventory.push(world.items.add(magical_wand));
-    player.inventory.push(world.items.add(some_money));
+    player.coins = 42;
     player.inventory.push(world.items.add(healing_potion));
     player.inventory.push(world.items.add(swimming_potion));
     player.inventory.push(world.items.add(vision_potion));
     player.inventory.push(world.items.add(key));
 
+    // some equipment to try on
+    player.inventory.push(world.items.add(Item::new(ItemKind::Sword).with_owner(player_id)));
+    player.inventory.push(world.items.add(Item::new(ItemKind::ClimbingBoots).with_owner(player_id)));
+    // the nights are dark
+    player.inventory.push(world.items.add(Item::new(ItemKind::Torch).with_owner(player_id)));
+    world.items.add(Item::new(ItemKind::Shield).with_pos(player.pos + Point::from((2, 0))));
+    world.items.add(Item::new(ItemKind::RingOfVision).with_pos(player.pos + Point::from((2, 0))));
+
     // spawn some more items on the map (just as an example)
-    world.items.add(Item::new(ItemKind::Money(10)).with_pos((5, 6)));
-    world.items.add(Item::new(ItemKind::Wand).with_pos((12, 10)));
-    world.items.add(Item::new(ItemKind::Wand).with_pos((5, 6)));
+    world.items.add(Item::new(ItemKind::Money).with_quantity(10).with_pos((5, 6)));
+    world.items.add(Item::new(ItemKind::Wand(Spell::Light)).with_pos((12, 10)));
+    world.items.add(Item::new(ItemKind::Wand(Spell::Teleport)).with_pos((5, 6)));
     world.items.add(Item::new(ItemKind::Gold).with_pos(player.pos));
     world.items.add(Item::new(ItemKind::Ore).with_pos(player.pos));
-    world.items.add(Item::new(ItemKind::Bread).with_pos(player.pos));
-    world.items.add(Item::new(ItemKind::Money(20)).with_pos(player.pos));
-    world.items.add(Item::new(ItemKind::Wand).with_pos(player.pos));
+    world.items.add(Item::new(ItemKind::Bread).with_quantity(2).with_pos(player.pos));
+    world.items.add(Item::new(ItemKind::Money).with_quantity(20).with_pos(player.pos));
+    world.items.add(Item::new(ItemKind::Wand(Spell::Dig)).with_pos(player.pos));
+    // tools to dig, cut and build a way through
+    world.items.add(Item::new(ItemKind::Pickaxe).with_pos(player.pos + Point::from((2, 0))));
+    world.items.add(Item::new(ItemKind::Axe).with_pos(player.pos + Point::from((2, 0))));
+
+    // a barrel filled with some food and a locked chest with a
+    // treasure, the key for the chest is hidden in the barrel, which
+    // belongs to the villagers
+    let bread = world.items.add(Item::new(ItemKind::Bread));
+    let chest_key = world.items.add(Item::new(ItemKind::Key(2)));
+    world.items.add(Item::new(ItemKind::Barrel)
+                    .with_pos(player.pos + Point::from((0, 1)))
+                    .with_content(vec![bread, chest_key])
+                    .with_faction(Faction::Villagers));
+    let gold = world.items.add(Item::new(ItemKind::Gold));
+    let potion = world.items.add(Item::new(ItemKind::Potion(Potion::Vision)));
+    world.items.add(Item::new(ItemKind::Chest)
+                    .with_pos(player.pos + Point::from((-1, 1)))
+                    .with_content(vec![gold, potion])
+                    .with_lock(2));
+
+    // the villagers meet at the fountain
+    let fountain = world.terrain.iter()
+        .find(|(_, tile)| tile.feature == Some(TerrainFeature::Fountain))
+        .map(|(pos, _)| *pos)
+        .unwrap_or(player.pos);
 
     // add shopkeeper next to the player, so that we can immediately go shopping
     let pos = player.pos + Point::from((1,0));
-    let shopkeeper = Actor::new(ActorKind::Shopkeeper, pos, 4)
-        .with_ai(ActorAI::DoNothing);
+    let mut shopkeeper = Actor::new(ActorKind::Shopkeeper, pos, 4)
+        .with_ai(ActorAI::FollowSchedule {
+            schedule: Schedule::new()
+                .at(8, Activity::Work, pos)
+                .at(18, Activity::Gather, fountain)
+                .at(21, Activity::Sleep, pos)
+        })
+        .with_dialogue("shopkeeper")
+        .with_shop(Shop::new(vec![
+            (ItemKind::Bread, 5),
+            (ItemKind::Potion(Potion::Healing), 3),
+            (ItemKind::Potion(Potion::Swimming), 1),
+            (ItemKind::Potion(Potion::Climbing), 1),
+            (ItemKind::Sword, 1),
+            (ItemKind::Armour, 1),
+            (ItemKind::Helmet, 1),
+            (ItemKind::Lantern, 1),
+            (ItemKind::Planks, 3),
+        ]).with_opening_hours(8, 18));
+    shopkeeper.coins = 200;
     
     world.actors.add(shopkeeper);
+    world.restock_shops();
+
+    // the shopkeeper's anvil and hammer, free to use
+    if let Some(tile) = world.terrain.get_mut(&(pos + Point::from((0, -1)))) {
+        tile.feature = Some(TerrainFeature::Anvil);
+    }
+    world.items.add(Item::new(ItemKind::Hammer).with_pos(pos + Point::from((1, -1))));
+    world.items.add(Item::new(ItemKind::Ore).with_quantity(3).with_pos(pos + Point::from((1, -1))));
+
+    let player_pos = world.player_pos();
+
+    // a cat and a dog are roaming around; feeding them with bread
+    // makes them follow the player
+    world.actors.add(Actor::new(ActorKind::Cat, player_pos + Point::from((-2, 2)), 3));
+    world.actors.add(Actor::new(ActorKind::Dog, player_pos + Point::from((2, 2)), 4));
 
     // spawn some random NPCs
     
@@ -95,7 +199,7 @@ pub fn populate_world(world: &mut World) {
         .collect::<Vec<&Point>>();
     
     let mut rng = rand::thread_rng();
-    for _ in 0..max_npc {
+    for n in 0..max_npc {
         let len = slots.len();
         if len == 0 {
             break;
@@ -106,7 +210,22 @@ pub fn populate_world(world: &mut World) {
             ActorKind::Townsfolk,
             actor_pos.clone(),
             rng.gen_range(5..8)
-        );
+        ).with_dialogue("townsfolk");
+        // the first villager patrols between its home and the town
+        // centre, the others go about their daily business
+        let new_actor = match n {
+            0 => new_actor.with_ai(ActorAI::Patrol {
+                waypoints: vec![actor_pos.clone(), player_pos],
+                next: 0
+            }),
+            _ => new_actor.with_ai(ActorAI::FollowSchedule {
+                schedule: Schedule::new()
+                    .at(7, Activity::Stroll, player_pos)
+                    .at(12, Activity::Gather, fountain)
+                    .at(14, Activity::Stroll, actor_pos.clone())
+                    .at(21, Activity::Sleep, actor_pos.clone())
+            })
+        };
         world.actors.add(new_actor);
         slots.remove(index);
     }
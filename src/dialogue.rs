@@ -0,0 +1,446 @@
+//! Branching dialogues between the player and NPCs.
+//!
+//! A Dialogue is a graph of nodes. Each node has a text, which is
+//! said by the NPC, and a list of choices for the player. A choice
+//! leads to another node (or ends the conversation) and may require
+//! some conditions and trigger some effects.
+//!
+//! Dialogues are read from text files, e.g.
+//!
+//! ```text
+//! # the conversation always begins with the node `start`
+//! node start
+//! say » Nice to meet you! «
+//! choice Can you spare some bread? -> bread
+//! if not_flag got_bread
+//! choice Goodbye. -> end
+//!
+//! node bread
+//! say » Here you are. «
+//! choice Thank you! -> end
+//! do give_item bread
+//! do set_flag got_bread
+//! ```
+//!
+//! Lines starting with `#` are comments. The `if` and `do` lines
+//! belong to the preceding choice. The target `end` closes the
+//! dialogue.
+//!
+
+use crate::{
+    world::World,
+    actor::ActorId,
+    item::{Item, ItemKind},
+    message::MessageKind,
+    event::Acquisition,
+    quest::QuestStatus
+};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const START_NODE: &str = "start";
+pub const END_NODE: &str = "end";
+
+#[derive(Debug, Clone, Default)]
+pub struct Dialogue {
+    pub nodes: HashMap<String, DialogueNode>
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DialogueNode {
+    pub text: String,
+    pub choices: Vec<Choice>
+}
+
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub text: String,
+    pub target: String,
+    pub conditions: Vec<Condition>,
+    pub effects: Vec<Effect>
+}
+
+/// Condition that must be met by the player so that a choice is
+/// available.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    HasItem(ItemKind),
+    Coins(u16),
+    Flag(String),
+    NotFlag(String),
+    /// the quest has been started
+    Quest(String),
+    /// the quest has not been started yet
+    NoQuest(String),
+    QuestCompleted(String),
+    /// the actor carries items that have not been identified
+    Unidentified
+}
+
+/// Effect of a choice. Opening a shop concerns the user interface
+/// and must be handled by the caller.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    GiveItem(ItemKind),
+    TakeItem(ItemKind),
+    GiveCoins(u16),
+    TakeCoins(u16),
+    SetFlag(String),
+    OpenShop,
+    StartQuest(String),
+    /// identify all items in the actor's inventory
+    Identify
+}
+
+#[derive(Debug)]
+pub enum DialogueError {
+    Io(std::io::Error),
+    Parse { line: usize, reason: String },
+    MissingStart,
+    UnknownTarget { node: String, target: String },
+    Unreachable { nodes: Vec<String> }
+}
+
+impl From<std::io::Error> for DialogueError {
+    fn from(error: std::io::Error) -> Self {
+        DialogueError::Io(error)
+    }
+}
+
+impl Dialogue {
+    /// Parse the dialogue from the given text. The dialogue is not
+    /// validated.
+    pub fn parse(text: &str) -> Result<Dialogue, DialogueError> {
+        let mut dialogue = Dialogue::default();
+        let mut current: Option<String> = None;
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |reason: &str| DialogueError::Parse { line: n + 1, reason: reason.into() };
+            let (keyword, rest) = match line.split_once(' ') {
+                Some((keyword, rest)) => (keyword, rest.trim()),
+                None => (line, "")
+            };
+
+            if keyword == "node" {
+                if rest.is_empty() {
+                    return Err(error("node without name"));
+                }
+                if dialogue.nodes.contains_key(rest) {
+                    return Err(error("duplicate node"));
+                }
+                dialogue.nodes.insert(rest.into(), DialogueNode::default());
+                current = Some(rest.into());
+                continue;
+            }
+
+            let node = current.as_ref()
+                .and_then(|id| dialogue.nodes.get_mut(id))
+                .ok_or_else(|| error("line outside of a node"))?;
+
+            match keyword {
+                "say" => {
+                    if !node.text.is_empty() {
+                        node.text.push('\n');
+                    }
+                    node.text.push_str(rest);
+                },
+                "choice" => {
+                    let (text, target) = rest.rsplit_once("->")
+                        .ok_or_else(|| error("choice without target"))?;
+                    node.choices.push(Choice {
+                        text: text.trim().into(),
+                        target: target.trim().into(),
+                        conditions: vec!(),
+                        effects: vec!()
+                    });
+                },
+                "if" => {
+                    let condition = Condition::parse(rest)
+                        .ok_or_else(|| error("unknown condition"))?;
+                    node.choices.last_mut()
+                        .ok_or_else(|| error("condition without choice"))?
+                        .conditions.push(condition);
+                },
+                "do" => {
+                    let effect = Effect::parse(rest)
+                        .ok_or_else(|| error("unknown effect"))?;
+                    node.choices.last_mut()
+                        .ok_or_else(|| error("effect without choice"))?
+                        .effects.push(effect);
+                },
+                _ => return Err(error("unknown keyword"))
+            }
+        }
+
+        Ok(dialogue)
+    }
+
+    /// Check that the dialogue has a start node, that all choices
+    /// lead to existing nodes and that every node can be reached
+    /// from the start node.
+    pub fn validate(&self) -> Result<(), DialogueError> {
+        if !self.nodes.contains_key(START_NODE) {
+            return Err(DialogueError::MissingStart);
+        }
+
+        for (id, node) in &self.nodes {
+            for choice in &node.choices {
+                if choice.target != END_NODE && !self.nodes.contains_key(&choice.target) {
+                    return Err(DialogueError::UnknownTarget {
+                        node: id.clone(),
+                        target: choice.target.clone()
+                    });
+                }
+            }
+        }
+
+        let mut reached = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(START_NODE);
+        while let Some(id) = queue.pop_front() {
+            if !reached.insert(id) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(id) {
+                for choice in &node.choices {
+                    queue.push_back(&choice.target);
+                }
+            }
+        }
+
+        let mut unreachable = self.nodes.keys()
+            .filter(|id| !reached.contains(id.as_str()))
+            .cloned()
+            .collect::<Vec<String>>();
+        if !unreachable.is_empty() {
+            unreachable.sort();
+            return Err(DialogueError::Unreachable { nodes: unreachable });
+        }
+
+        Ok(())
+    }
+
+    pub fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.get(id)
+    }
+}
+
+impl DialogueNode {
+    /// Return the choices (together with their index) whose
+    /// conditions are met by the given actor.
+    pub fn available_choices<'a>(&'a self, world: &'a World, actor_id: &'a ActorId)
+                                 -> impl Iterator<Item=(usize, &'a Choice)> {
+        self.choices.iter().enumerate()
+            .filter(move |(_, choice)| choice.conditions.iter()
+                    .all(|condition| condition.is_met(world, actor_id)))
+    }
+}
+
+impl Condition {
+    fn parse(text: &str) -> Option<Condition> {
+        let (keyword, arg) = match text.split_once(' ') {
+            Some((keyword, arg)) => (keyword, arg.trim()),
+            None => (text, "")
+        };
+        match keyword {
+            "has_item" => ItemKind::from_name(arg).map(Condition::HasItem),
+            "coins" => arg.parse().ok().map(Condition::Coins),
+            "flag" if !arg.is_empty() => Some(Condition::Flag(arg.into())),
+            "not_flag" if !arg.is_empty() => Some(Condition::NotFlag(arg.into())),
+            "quest" if !arg.is_empty() => Some(Condition::Quest(arg.into())),
+            "no_quest" if !arg.is_empty() => Some(Condition::NoQuest(arg.into())),
+            "quest_completed" if !arg.is_empty() => Some(Condition::QuestCompleted(arg.into())),
+            "unidentified" => Some(Condition::Unidentified),
+            _ => None
+        }
+    }
+
+    pub fn is_met(&self, world: &World, actor_id: &ActorId) -> bool {
+        match self {
+            Condition::HasItem(kind) => find_item(world, actor_id, kind).is_some(),
+            Condition::Coins(amount) => world.actors.get(actor_id)
+                .map_or(false, |actor| actor.coins >= *amount),
+            Condition::Flag(flag) => world.flags.contains(flag),
+            Condition::NotFlag(flag) => !world.flags.contains(flag),
+            Condition::Quest(quest) => world.quest_status(quest).is_some(),
+            Condition::NoQuest(quest) => world.quest_status(quest).is_none(),
+            Condition::QuestCompleted(quest) => world.quest_status(quest) == Some(QuestStatus::Completed),
+            Condition::Unidentified => world.actors.get(actor_id)
+                .map_or(false, |actor| actor.inventory.iter()
+                        .filter_map(|id| world.items.get(id))
+                        .any(|item| !world.known_items.is_known(&item.kind))),
+        }
+    }
+}
+
+impl Effect {
+    fn parse(text: &str) -> Option<Effect> {
+        let (keyword, arg) = match text.split_once(' ') {
+            Some((keyword, arg)) => (keyword, arg.trim()),
+            None => (text, "")
+        };
+        match keyword {
+            "give_item" => ItemKind::from_name(arg).map(Effect::GiveItem),
+            "take_item" => ItemKind::from_name(arg).map(Effect::TakeItem),
+            "give_coins" => arg.parse().ok().map(Effect::GiveCoins),
+            "take_coins" => arg.parse().ok().map(Effect::TakeCoins),
+            "set_flag" if !arg.is_empty() => Some(Effect::SetFlag(arg.into())),
+            "open_shop" => Some(Effect::OpenShop),
+            "start_quest" if !arg.is_empty() => Some(Effect::StartQuest(arg.into())),
+            "identify" => Some(Effect::Identify),
+            _ => None
+        }
+    }
+
+    /// Apply the effect on the given actor.
+    pub fn apply(&self, world: &mut World, actor_id: &ActorId) {
+        match self {
+            Effect::GiveItem(kind) => {
+                world.acquire_item(actor_id, Item::new(kind.clone()), Acquisition::Gift);
+            },
+            Effect::TakeItem(kind) => {
+                if let Some(item_id) = find_item(world, actor_id, kind) {
+                    let description = world.describe_item(&Item::new(kind.clone()));
+                    world.messages.push((MessageKind::Inventory, format!("You hand over {}.", description)));
+                    world.take_one(&item_id);
+                }
+            },
+            Effect::GiveCoins(amount) => {
+                if let Some(actor) = world.actors.get_mut(actor_id) {
+                    actor.coins += amount;
+                    world.messages.push((MessageKind::Inventory, format!("You receive {} coins.", amount)));
+                }
+            },
+            Effect::TakeCoins(amount) => {
+                if let Some(actor) = world.actors.get_mut(actor_id) {
+                    actor.coins = actor.coins.saturating_sub(*amount);
+                    world.messages.push((MessageKind::Inventory, format!("You pay {} coins.", amount)));
+                }
+            },
+            Effect::SetFlag(flag) => {
+                world.set_flag(flag);
+            },
+            Effect::StartQuest(quest) => {
+                world.start_quest(quest);
+            },
+            Effect::Identify => {
+                let kinds = world.actors.get(actor_id)
+                    .map(|actor| actor.inventory.iter()
+                         .filter_map(|id| world.items.get(id))
+                         .map(|item| item.kind.clone())
+                         .collect::<Vec<ItemKind>>())
+                    .unwrap_or_default();
+                for kind in kinds {
+                    world.identify(&kind);
+                }
+            },
+            Effect::OpenShop => {}
+        }
+    }
+}
+
+/// Return the id of an item of the given kind in the actor's inventory.
+fn find_item(world: &World, actor_id: &ActorId, kind: &ItemKind) -> Option<crate::item::ItemId> {
+    world.actors.get(actor_id)?.inventory.iter()
+        .find(|id| world.items.get(id).map_or(false, |item| item.kind == *kind))
+        .cloned()
+}
+
+/// Read a dialogue from the given file and validate it.
+pub fn read_from_file<P>(path: P) -> Result<Dialogue, DialogueError>
+where P: AsRef<std::path::Path>
+{
+    let text = std::fs::read_to_string(path)?;
+    let dialogue = Dialogue::parse(&text)?;
+    dialogue.validate()?;
+    Ok(dialogue)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "
+# a small sample dialogue
+node start
+say Hello!
+choice Can I have some bread? -> bread
+if coins 2
+choice Bye. -> end
+
+node bread
+say Here you are.
+choice Thanks. -> end
+do take_coins 2
+do give_item bread
+";
+
+    #[test]
+    fn parse_sample() {
+        let dialogue = Dialogue::parse(SAMPLE).unwrap();
+        assert!(dialogue.validate().is_ok());
+
+        let start = dialogue.node(START_NODE).unwrap();
+        assert_eq!(start.text, "Hello!");
+        assert_eq!(start.choices.len(), 2);
+        assert_eq!(start.choices[0].target, "bread");
+        assert_eq!(start.choices[0].conditions, vec![Condition::Coins(2)]);
+
+        let bread = dialogue.node("bread").unwrap();
+        assert_eq!(bread.choices[0].effects,
+                   vec![Effect::TakeCoins(2), Effect::GiveItem(ItemKind::Bread)]);
+    }
+
+    #[test]
+    fn parse_error_reports_line() {
+        let result = Dialogue::parse("node start\nchoice no target here");
+        assert!(matches!(result, Err(DialogueError::Parse { line: 2, .. })));
+        let result = Dialogue::parse("node start\nsay Hello.\nnode start");
+        assert!(matches!(result, Err(DialogueError::Parse { line: 3, .. })));
+    }
+
+    #[test]
+    fn detect_unknown_target() {
+        let dialogue = Dialogue::parse("node start\nchoice Go. -> nowhere").unwrap();
+        assert!(matches!(dialogue.validate(), Err(DialogueError::UnknownTarget { .. })));
+    }
+
+    #[test]
+    fn detect_unreachable_nodes() {
+        let dialogue = Dialogue::parse(
+            "node start\nchoice Bye. -> end\nnode lost\nchoice Bye. -> end"
+        ).unwrap();
+        match dialogue.validate() {
+            Err(DialogueError::Unreachable { nodes }) => assert_eq!(nodes, vec!["lost".to_string()]),
+            result => panic!("unexpected result {:?}", result)
+        }
+    }
+
+    #[test]
+    fn choices_depend_on_conditions() {
+        let dialogue = Dialogue::parse(SAMPLE).unwrap();
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let start = dialogue.node(START_NODE).unwrap();
+
+        assert_eq!(start.available_choices(&world, &player_id).count(), 1);
+        world.actors.get_mut(&player_id).unwrap().coins = 2;
+        assert_eq!(start.available_choices(&world, &player_id).count(), 2);
+    }
+
+    #[test]
+    fn bundled_dialogues_are_valid() {
+        for name in &["townsfolk", "shopkeeper"] {
+            let path = format!("assets/dialogues/{}.dlg", name);
+            if let Err(error) = read_from_file(&path) {
+                panic!("{}: {:?}", path, error);
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Equipment slots of an Actor.
+//!
+//! An item that is worn or wielded is moved from the actor's
+//! inventory into one of the slots. While it is equipped, the item's
+//! `StatModifier` applies to the actor.
+//!
+
+use crate::{
+    item::ItemId,
+    skill::SkillKind
+};
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum EquipmentSlot {
+    Weapon,
+    OffHand,
+    Body,
+    Head,
+    Feet,
+    Ring,
+    Amulet
+}
+
+impl EquipmentSlot {
+    pub const ALL: [EquipmentSlot; 7] = [
+        EquipmentSlot::Weapon,
+        EquipmentSlot::OffHand,
+        EquipmentSlot::Body,
+        EquipmentSlot::Head,
+        EquipmentSlot::Feet,
+        EquipmentSlot::Ring,
+        EquipmentSlot::Amulet
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            EquipmentSlot::Weapon => "weapon",
+            EquipmentSlot::OffHand => "off-hand",
+            EquipmentSlot::Body => "body",
+            EquipmentSlot::Head => "head",
+            EquipmentSlot::Feet => "feet",
+            EquipmentSlot::Ring => "ring",
+            EquipmentSlot::Amulet => "amulet",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Equipment {
+    slots: HashMap<EquipmentSlot, ItemId>
+}
+
+impl Equipment {
+    /// Return the id of the item in the given slot.
+    pub fn get(&self, slot: &EquipmentSlot) -> Option<&ItemId> {
+        self.slots.get(slot)
+    }
+
+    /// Put the item into the given slot. If the slot was already
+    /// occupied, the previous item is returned.
+    pub fn equip(&mut self, slot: EquipmentSlot, item_id: ItemId) -> Option<ItemId> {
+        self.slots.insert(slot, item_id)
+    }
+
+    /// Remove the item from the given slot and return it.
+    pub fn unequip(&mut self, slot: &EquipmentSlot) -> Option<ItemId> {
+        self.slots.remove(slot)
+    }
+
+    /// Return an iterator over all equipped items.
+    pub fn items(&self) -> impl Iterator<Item=&ItemId> {
+        self.slots.values()
+    }
+}
+
+/// Bonus granted by an equipped item.
+#[derive(Debug, Clone, Default)]
+pub struct StatModifier {
+    pub attack: i32,
+    pub defense: i32,
+    pub skills: Vec<SkillKind>
+}
+
+impl StatModifier {
+    pub fn attack(attack: i32) -> Self {
+        Self { attack, ..Default::default() }
+    }
+
+    pub fn defense(defense: i32) -> Self {
+        Self { defense, ..Default::default() }
+    }
+
+    pub fn skill(kind: SkillKind) -> Self {
+        Self { skills: vec![kind], ..Default::default() }
+    }
+}
+
+impl std::ops::AddAssign for StatModifier {
+    fn add_assign(&mut self, other: Self) {
+        self.attack += other.attack;
+        self.defense += other.defense;
+        self.skills.extend(other.skills);
+    }
+}
@@ -0,0 +1,100 @@
+//! Game events.
+//!
+//! The game core emits a GameEvent whenever something noteworthy
+//! happens in the World. The events are collected in the EventBus
+//! and dispatched once per frame (see `World::dispatch_events`), so
+//! that other systems can react on them without being called from
+//! the place where the world is changed.
+//!
+
+use crate::{
+    point::Point,
+    actor::{ActorId, ActorKind},
+    item::{ItemId, ItemKind},
+    terrain::LockId
+};
+
+use std::collections::VecDeque;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    ActorMoved { actor_id: ActorId, from: Point, to: Point },
+    ActorDied { actor_id: ActorId, kind: ActorKind },
+    ActorTalked { actor_id: ActorId, partner_id: ActorId, partner_kind: ActorKind },
+    ItemPickedUp { actor_id: ActorId, item_id: ItemId, kind: ItemKind },
+    /// the item has been put into the actor's inventory
+    ItemReceived { actor_id: ActorId, item_id: ItemId, kind: ItemKind, quantity: u16, acquisition: Acquisition },
+    ItemDropped { actor_id: ActorId, item_id: ItemId, kind: ItemKind },
+    ItemUsed { actor_id: ActorId, item_id: ItemId, kind: ItemKind },
+    DoorOpened { actor_id: ActorId, pos: Point },
+    DoorClosed { actor_id: ActorId, pos: Point },
+    DoorUnlocked { actor_id: ActorId, pos: Point, lock: LockId },
+    /// the terrain has been altered, e.g. by digging through a wall
+    TerrainChanged { actor_id: ActorId, pos: Point },
+    ContainerUnlocked { actor_id: ActorId, container_id: ItemId, lock: LockId },
+    FlagSet { flag: String },
+    QuestStarted { quest_id: String },
+    QuestCompleted { quest_id: String },
+    TurnEnded { time: i32 }
+}
+
+/// How an actor came by an item that it received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acquisition {
+    /// a present, e.g. a quest reward
+    Gift,
+    /// bought for the given price
+    Purchase(u16),
+    Craft,
+    /// an empty bottle filled up by the rain
+    Rain,
+    /// anything that does not need to be mentioned, e.g. a bottle
+    /// that is emptied by drinking
+    Other
+}
+
+pub type Subscriber = Box<dyn FnMut(&GameEvent)>;
+
+#[derive(Default)]
+pub struct EventBus {
+    pending: VecDeque<GameEvent>,
+    subscribers: Vec<Subscriber>
+}
+
+impl EventBus {
+    /// Queue the event. It is passed to the subscribers with the
+    /// next dispatch.
+    pub fn emit(&mut self, event: GameEvent) {
+        self.pending.push_back(event);
+    }
+
+    /// Register a function that is called for every dispatched event.
+    #[allow(dead_code)]
+    pub fn subscribe<F>(&mut self, subscriber: F)
+    where F: FnMut(&GameEvent) + 'static
+    {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    /// Remove and return the next pending event.
+    pub fn next(&mut self) -> Option<GameEvent> {
+        self.pending.pop_front()
+    }
+
+    /// Pass the event to all subscribers.
+    pub fn notify(&mut self, event: &GameEvent) {
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(event);
+        }
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("pending", &self.pending)
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
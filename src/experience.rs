@@ -0,0 +1,117 @@
+//! Experience, levels and practiced skills.
+//!
+//! The player gains experience points by exploring the world, by
+//! completing quests and by defeating other actors. Enough experience
+//! leads to the next level, which raises the maximum health.
+//!
+//! Skills that are used regularly are practiced. Every few uses
+//! increase the rank of the skill, and each rank lowers the chance to
+//! fail, e.g. to struggle while swimming.
+//!
+
+use crate::actor::ActorKind;
+
+/// Additional maximum health gained with every level.
+pub const HEALTH_PER_LEVEL: u16 = 2;
+/// Number of newly discovered tiles that give one experience point.
+pub const TILES_PER_POINT: u32 = 20;
+/// Highest rank a skill can be practiced to.
+pub const MAX_RANK: u16 = 5;
+/// Number of uses it takes to reach the next rank.
+const PRACTICE_PER_RANK: u16 = 10;
+/// Chance that using an unpracticed skill fails.
+const BASE_FAILURE_CHANCE: f32 = 0.25;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Experience {
+    pub level: u16,
+    /// experience points gained on the current level
+    pub points: u32,
+    /// discovered tiles that have not yet been turned into points
+    explored: u32
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            points: 0,
+            explored: 0
+        }
+    }
+}
+
+impl Experience {
+    /// Experience points needed to advance from the current level.
+    pub fn next_level(&self) -> u32 {
+        20 * self.level as u32
+    }
+
+    /// Add the points and return the number of levels gained.
+    pub fn gain(&mut self, points: u32) -> u16 {
+        let mut levels = 0;
+        self.points += points;
+        while self.points >= self.next_level() {
+            self.points -= self.next_level();
+            self.level += 1;
+            levels += 1;
+        }
+        levels
+    }
+
+    /// Count newly discovered tiles and return the experience points
+    /// earned by them.
+    pub fn explore(&mut self, tiles: u32) -> u32 {
+        self.explored += tiles;
+        let points = self.explored / TILES_PER_POINT;
+        self.explored %= TILES_PER_POINT;
+        points
+    }
+}
+
+/// Experience points for defeating an actor of the given kind.
+pub fn kill_points(kind: &ActorKind) -> u32 {
+    match kind {
+        ActorKind::Cat | ActorKind::Dog => 5,
+        ActorKind::Townsfolk | ActorKind::Shopkeeper => 10,
+        ActorKind::Player => 0
+    }
+}
+
+/// Rank of a skill that has been used `practice` times.
+pub fn rank(practice: u16) -> u16 {
+    (practice / PRACTICE_PER_RANK).min(MAX_RANK)
+}
+
+/// Chance that using a skill of the given rank fails. A skill that
+/// has been practiced to the highest rank never fails.
+pub fn failure_chance(rank: u16) -> f32 {
+    BASE_FAILURE_CHANCE * (MAX_RANK - rank.min(MAX_RANK)) as f32 / MAX_RANK as f32
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_levels() {
+        let mut experience = Experience::default();
+        assert_eq!(experience.gain(19), 0);
+        assert_eq!(experience.gain(1), 1);
+        assert_eq!(experience.level, 2);
+        assert_eq!(experience.points, 0);
+        // 40 points for level 3, 60 for level 4
+        assert_eq!(experience.gain(105), 2);
+        assert_eq!(experience, Experience { level: 4, points: 5, explored: 0 });
+    }
+
+    #[test]
+    fn practice_lowers_failure_chance() {
+        assert_eq!(rank(9), 0);
+        assert_eq!(rank(10), 1);
+        assert_eq!(rank(1000), MAX_RANK);
+        assert!(failure_chance(1) < failure_chance(0));
+        assert_eq!(failure_chance(MAX_RANK), 0.0);
+    }
+}
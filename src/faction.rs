@@ -0,0 +1,177 @@
+//! Factions, attitudes and the player's reputation.
+//!
+//! Every NPC may belong to a Faction. Factions have fixed attitudes
+//! towards each other, e.g. villagers and bandits are hostile. The
+//! attitude of a faction towards the player depends on the player's
+//! reputation with that faction, which changes with the player's
+//! deeds.
+//!
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Faction {
+    Villagers,
+    Guards,
+    Wildlife,
+    Bandits
+}
+
+impl Faction {
+    pub const ALL: [Faction; 4] = [
+        Faction::Villagers,
+        Faction::Guards,
+        Faction::Wildlife,
+        Faction::Bandits
+    ];
+
+    /// Return the faction with the given name, as used in data files.
+    pub fn from_name(name: &str) -> Option<Faction> {
+        match name {
+            "villagers" => Some(Faction::Villagers),
+            "guards" => Some(Faction::Guards),
+            "wildlife" => Some(Faction::Wildlife),
+            "bandits" => Some(Faction::Bandits),
+            _ => None
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Faction::Villagers => "villagers",
+            Faction::Guards => "guards",
+            Faction::Wildlife => "wildlife",
+            Faction::Bandits => "bandits"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Attitude {
+    Friendly,
+    Neutral,
+    Hostile
+}
+
+/// Something the player did that changes the reputation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deed {
+    Theft,
+    Assault,
+    Murder
+}
+
+impl Deed {
+    /// Change of reputation with the victim's faction.
+    pub fn reputation_change(&self) -> i32 {
+        match self {
+            Deed::Theft => -15,
+            Deed::Assault => -20,
+            Deed::Murder => -50
+        }
+    }
+}
+
+/// Reputation at or above which a faction is friendly to the player.
+pub const FRIENDLY_REPUTATION: i32 = 25;
+/// Reputation at or below which a faction is hostile to the player.
+pub const HOSTILE_REPUTATION: i32 = -25;
+
+#[derive(Debug, Clone)]
+pub struct Factions {
+    attitudes: HashMap<(Faction, Faction), Attitude>,
+    reputation: HashMap<Faction, i32>
+}
+
+impl Default for Factions {
+    fn default() -> Self {
+        let mut factions = Self {
+            attitudes: HashMap::new(),
+            reputation: HashMap::new()
+        };
+        factions.set_attitude(Faction::Villagers, Faction::Guards, Attitude::Friendly);
+        factions.set_attitude(Faction::Villagers, Faction::Bandits, Attitude::Hostile);
+        factions.set_attitude(Faction::Guards, Faction::Bandits, Attitude::Hostile);
+        factions.reputation.insert(Faction::Bandits, -50);
+        factions
+    }
+}
+
+impl Factions {
+    /// Set the attitude of both factions towards each other.
+    pub fn set_attitude(&mut self, a: Faction, b: Faction, attitude: Attitude) {
+        self.attitudes.insert((a, b), attitude);
+        self.attitudes.insert((b, a), attitude);
+    }
+
+    /// Return the attitude between two factions. Members of the same
+    /// faction are friendly, everyone else is neutral by default.
+    pub fn attitude(&self, a: Faction, b: Faction) -> Attitude {
+        if a == b {
+            return Attitude::Friendly;
+        }
+        self.attitudes.get(&(a, b)).cloned().unwrap_or(Attitude::Neutral)
+    }
+
+    pub fn reputation(&self, faction: Faction) -> i32 {
+        self.reputation.get(&faction).cloned().unwrap_or(0)
+    }
+
+    /// Change the player's reputation with the faction, which is
+    /// limited to -100..=100.
+    pub fn change_reputation(&mut self, faction: Faction, amount: i32) {
+        let reputation = self.reputation.entry(faction).or_insert(0);
+        *reputation = (*reputation + amount).max(-100).min(100);
+    }
+
+    /// Record a deed against a member of the faction. Friends of the
+    /// faction take offense as well, but only half as much.
+    pub fn record_deed(&mut self, deed: Deed, victim: Faction) {
+        let change = deed.reputation_change();
+        for faction in Faction::ALL.iter() {
+            if *faction == victim {
+                self.change_reputation(*faction, change);
+            } else if self.attitude(*faction, victim) == Attitude::Friendly {
+                self.change_reputation(*faction, change / 2);
+            }
+        }
+    }
+
+    /// Return the attitude of the faction towards the player.
+    pub fn attitude_to_player(&self, faction: Faction) -> Attitude {
+        match self.reputation(faction) {
+            r if r >= FRIENDLY_REPUTATION => Attitude::Friendly,
+            r if r <= HOSTILE_REPUTATION => Attitude::Hostile,
+            _ => Attitude::Neutral
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attitudes_are_symmetric() {
+        let factions = Factions::default();
+        assert_eq!(factions.attitude(Faction::Bandits, Faction::Villagers), Attitude::Hostile);
+        assert_eq!(factions.attitude(Faction::Villagers, Faction::Bandits), Attitude::Hostile);
+        assert_eq!(factions.attitude(Faction::Wildlife, Faction::Guards), Attitude::Neutral);
+    }
+
+    #[test]
+    fn deeds_anger_friends_of_the_victim() {
+        let mut factions = Factions::default();
+        assert_eq!(factions.attitude_to_player(Faction::Villagers), Attitude::Neutral);
+        assert_eq!(factions.attitude_to_player(Faction::Bandits), Attitude::Hostile);
+
+        factions.record_deed(Deed::Assault, Faction::Villagers);
+        assert_eq!(factions.reputation(Faction::Villagers), -20);
+        assert_eq!(factions.reputation(Faction::Guards), -10);
+        assert_eq!(factions.reputation(Faction::Wildlife), 0);
+
+        factions.record_deed(Deed::Theft, Faction::Villagers);
+        assert_eq!(factions.attitude_to_player(Faction::Villagers), Attitude::Hostile);
+    }
+}
@@ -0,0 +1,141 @@
+//! Unidentified items.
+//!
+//! Potions and wands do not reveal what they are. Each game assigns
+//! random colours to the potions and random materials to the wands,
+//! so that e.g. all potions of healing are red in one game and green
+//! in the next. An item kind becomes known when it is used or when it
+//! is identified, e.g. by a shopkeeper.
+//!
+//! Unknown potions are drawn with the tile of a randomly assigned
+//! potion kind, so that the tile does not give them away either.
+//!
+
+use crate::{
+    item::{Item, ItemKind, Potion, item_index},
+    spell::Spell
+};
+
+use std::collections::{HashMap, HashSet};
+use rand::{Rng, seq::SliceRandom};
+
+const POTION_COLOURS: [&str; 6] = ["red", "blue", "green", "murky", "golden", "violet"];
+const WAND_MATERIALS: [&str; 6] = ["oak", "bone", "iron", "crystal", "copper", "ebony"];
+
+#[derive(Debug, Clone)]
+struct Appearance {
+    name: &'static str,
+    tile: usize
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KnownItems {
+    /// appearance of every item kind that must be identified
+    appearances: HashMap<ItemKind, Appearance>,
+    /// item kinds that have been identified
+    known: HashSet<ItemKind>
+}
+
+impl KnownItems {
+    /// Assign random appearances to the potions and wands.
+    pub fn new<R: Rng>(rng: &mut R) -> Self {
+        let mut colours = POTION_COLOURS.to_vec();
+        let mut materials = WAND_MATERIALS.to_vec();
+        colours.shuffle(rng);
+        materials.shuffle(rng);
+
+        let potions = [Potion::Vision, Potion::Healing, Potion::Swimming, Potion::Climbing].iter()
+            .map(|potion| ItemKind::Potion(potion.clone()))
+            .collect::<Vec<ItemKind>>();
+        let mut disguises = potions.clone();
+        disguises.shuffle(rng);
+        let wands = Spell::ALL.iter().map(|spell| ItemKind::Wand(*spell));
+
+        let mut appearances = HashMap::new();
+        for ((kind, disguise), name) in potions.into_iter().zip(disguises.into_iter()).zip(colours.into_iter()) {
+            let tile = item_index(&Item::new(disguise));
+            appearances.insert(kind, Appearance { name, tile });
+        }
+        for (kind, name) in wands.zip(materials.into_iter()) {
+            let tile = item_index(&Item::new(kind.clone()));
+            appearances.insert(kind, Appearance { name, tile });
+        }
+        Self {
+            appearances,
+            known: HashSet::new()
+        }
+    }
+
+    /// Return true if the player knows what items of this kind are.
+    pub fn is_known(&self, kind: &ItemKind) -> bool {
+        !self.appearances.contains_key(kind) || self.known.contains(kind)
+    }
+
+    /// Mark the item kind as known. Returns true if the kind has not
+    /// been known before.
+    pub fn identify(&mut self, kind: &ItemKind) -> bool {
+        !self.is_known(kind) && self.known.insert(kind.clone())
+    }
+
+    /// Return the tile index of the item as far as the player
+    /// knows it.
+    pub fn tile_index(&self, item: &Item) -> usize {
+        match self.appearance(&item.kind) {
+            Some(appearance) => appearance.tile,
+            None => item_index(item)
+        }
+    }
+
+    /// Return the appearance of an unknown item kind.
+    fn appearance(&self, kind: &ItemKind) -> Option<&Appearance> {
+        self.appearances.get(kind).filter(|_| !self.known.contains(kind))
+    }
+
+    /// Return the description of the item as far as the player
+    /// knows it.
+    pub fn describe(&self, item: &Item) -> String {
+        let appearance = match self.appearance(&item.kind) {
+            Some(appearance) => appearance,
+            None => return item.description()
+        };
+        let description = match item.kind {
+            ItemKind::Wand(_) => format!("a wand made of {}", appearance.name),
+            _ => format!("a {} potion", appearance.name)
+        };
+
+        if item.quantity > 1 {
+            format!("{} ({})", description, item.quantity)
+        } else {
+            description
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_items_are_described_by_appearance() {
+        let mut known = KnownItems::new(&mut rand::thread_rng());
+        let potion = Item::new(ItemKind::Potion(Potion::Healing)).with_quantity(2);
+        let key = Item::new(ItemKind::Key(1));
+
+        assert!(!known.describe(&potion).contains("healing"));
+        assert!(known.describe(&potion).ends_with("potion (2)"));
+        assert_eq!(known.describe(&key), key.description());
+        assert!(!known.identify(&key.kind));
+
+        assert!(known.identify(&potion.kind));
+        assert_eq!(known.describe(&potion), potion.description());
+        assert!(!known.identify(&potion.kind));
+    }
+
+    #[test]
+    fn appearances_differ() {
+        let known = KnownItems::new(&mut rand::thread_rng());
+        let healing = Item::new(ItemKind::Potion(Potion::Healing));
+        let vision = Item::new(ItemKind::Potion(Potion::Vision));
+        assert_ne!(known.describe(&healing), known.describe(&vision));
+    }
+}
@@ -1,11 +1,17 @@
 use crate::{
     point::{Point, PointSet},
-    actor::{ActorId},
+    actor::{ActorId, Inventory, Attribute, MAX_NUTRITION},
     idmap::{Id, IdMap},
     world::World,
     skill::{Skill, SkillKind, GameTime, SkillDuration},
     message::MessageKind,
-    terrain::{TerrainKind, DoorState, TerrainFeature}
+    equipment::{EquipmentSlot, StatModifier},
+    terrain::{TerrainKind, DoorState, TerrainFeature, LockId},
+    event::GameEvent,
+    faction::Faction,
+    spell::{self, Spell},
+    light::LightSource,
+    alteration::Alteration
 };
 
 use std::collections::HashSet;
@@ -13,34 +19,83 @@ use std::collections::HashSet;
 pub type ItemId = Id<Item>;
 pub type ItemMap = IdMap<Item>;
 
+/// Weight of an item, measured in units of 100g.
+pub type Weight = u32;
+
+/// Number of times a key can be used before it breaks.
+pub const KEY_DURABILITY: u16 = 5;
+/// Number of times a tool can be used before it breaks.
+pub const TOOL_DURABILITY: u16 = 10;
+
 
 #[derive(Debug, Clone)]
 pub struct Item {
     pub kind: ItemKind,
     pub pos: Option<Point>,
-    pub owner: Option<ActorId>
+    pub owner: Option<ActorId>,
+    pub container: Option<Container>,
+    pub quantity: u16,
+    /// number of uses left, e.g. of a wand
+    pub charges: Option<Attribute>,
+    /// uses left before the item breaks, e.g. of a key or a tool
+    pub durability: Option<Attribute>
 }
 
+/// A Container (e.g. a barrel or a chest) holds its own inventory of
+/// items. Items inside a container have neither a position nor an
+/// owner. If the container has a `lock`, then it must be unlocked
+/// with a matching key before it can be opened.
+#[derive(Debug, Clone, Default)]
+pub struct Container {
+    pub items: Inventory,
+    pub lock: Option<LockId>,
+    /// taking items out of a container that belongs to a faction is theft
+    pub faction: Option<Faction>
+}
+
+/// Items of the same kind can be stacked, if the kind is stackable
+/// (see `Item::is_stackable`). The size of a stack is given by the
+/// item's `quantity`.
+///
+/// Money is not kept in the inventory: picking up a pile of coins
+/// adds its quantity to the actor's `coins`, which is the currency
+/// used for trading.
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ItemKind {
-    Money(u16),
-    Wand,
+    Money,
+    Wand(Spell),
     Ore,
     Gold,
     Potion(Potion),
     Bread,
     Barrel,
-    Key
+    Chest,
+    Key(LockId),
+    Sword,
+    Shield,
+    Armour,
+    Helmet,
+    ClimbingBoots,
+    RingOfVision,
+    Hammer,
+    Torch,
+    Lantern,
+    Pickaxe,
+    Axe,
+    Planks,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Potion {
     Empty,
     Vision,
     Healing,
     Swimming,
+    Climbing,
+    /// collected from the rain
+    Water,
 }
 
 #[allow(dead_code)]
@@ -50,18 +105,147 @@ pub enum UseResult {
     Replace,
     Drop,
     Select { positions: PointSet },
+    /// aim at a position with a projectile
+    Target,
+    /// the item wears out a bit and breaks eventually, e.g. a key
+    Wear,
+    /// one of the item's charges is used up, e.g. of a wand
+    Discharge,
     Cancel
 }
 
+impl ItemKind {
+    /// Look up the item kind by the name that is used in data files,
+    /// e.g. `bread` or `potion_of_healing`. Keys are given together
+    /// with their lock id, e.g. `key:2`, and wands with their spell,
+    /// e.g. `wand_of_dig`.
+    pub fn from_name(name: &str) -> Option<ItemKind> {
+        if let Some(lock) = name.strip_prefix("key:") {
+            return lock.parse().ok().map(ItemKind::Key);
+        }
+        if let Some(spell) = name.strip_prefix("wand_of_") {
+            return Spell::from_name(spell).map(ItemKind::Wand);
+        }
+
+        let kind = match name {
+            "money" => ItemKind::Money,
+            "ore" => ItemKind::Ore,
+            "gold" => ItemKind::Gold,
+            "empty_potion" => ItemKind::Potion(Potion::Empty),
+            "potion_of_vision" => ItemKind::Potion(Potion::Vision),
+            "potion_of_healing" => ItemKind::Potion(Potion::Healing),
+            "potion_of_swimming" => ItemKind::Potion(Potion::Swimming),
+            "potion_of_climbing" => ItemKind::Potion(Potion::Climbing),
+            "potion_of_water" => ItemKind::Potion(Potion::Water),
+            "bread" => ItemKind::Bread,
+            "barrel" => ItemKind::Barrel,
+            "chest" => ItemKind::Chest,
+            "sword" => ItemKind::Sword,
+            "shield" => ItemKind::Shield,
+            "armour" => ItemKind::Armour,
+            "helmet" => ItemKind::Helmet,
+            "climbing_boots" => ItemKind::ClimbingBoots,
+            "ring_of_vision" => ItemKind::RingOfVision,
+            "hammer" => ItemKind::Hammer,
+            "torch" => ItemKind::Torch,
+            "lantern" => ItemKind::Lantern,
+            "pickaxe" => ItemKind::Pickaxe,
+            "axe" => ItemKind::Axe,
+            "planks" => ItemKind::Planks,
+            _ => return None
+        };
+        Some(kind)
+    }
+
+    /// Describe any item of this kind, e.g. regardless of the charges
+    /// left in a particular wand.
+    pub fn description(&self) -> String {
+        match self {
+            ItemKind::Wand(spell) => format!("a wand of {}", spell.description()),
+            ItemKind::Ore => format!("a chunk of ore"),
+            ItemKind::Gold => format!("a chunk of gold"),
+            ItemKind::Bread => format!("a loaf of bread"),
+            ItemKind::Money => format!("coins of gold"),
+            ItemKind::Potion(Potion::Empty) => format!("an empty potion"),
+            ItemKind::Potion(Potion::Healing) => format!("a potion of healing"),
+            ItemKind::Potion(Potion::Swimming) => format!("a potion of swimming"),
+            ItemKind::Potion(Potion::Climbing) => format!("a potion of climbing"),
+            ItemKind::Potion(Potion::Water) => format!("a bottle of water"),
+            ItemKind::Potion(Potion::Vision) => format!("a potion of vision"),
+            ItemKind::Barrel => format!("a wooden barrel"),
+            ItemKind::Chest => format!("a wooden chest"),
+            ItemKind::Sword => format!("a sword"),
+            ItemKind::Shield => format!("a wooden shield"),
+            ItemKind::Armour => format!("a leather armour"),
+            ItemKind::Helmet => format!("an iron helmet"),
+            ItemKind::ClimbingBoots => format!("a pair of climbing boots"),
+            ItemKind::RingOfVision => format!("a ring of vision"),
+            ItemKind::Hammer => format!("a smith's hammer"),
+            ItemKind::Torch => format!("a torch"),
+            ItemKind::Lantern => format!("a lantern"),
+            ItemKind::Pickaxe => format!("a pickaxe"),
+            ItemKind::Axe => format!("an axe"),
+            ItemKind::Planks => format!("some wooden planks"),
+            ItemKind::Key(_) => format!("a metal key"),
+        }
+    }
+}
+
 impl Item {
     pub fn new(kind: ItemKind) -> Self {
+        let container = match kind {
+            ItemKind::Barrel | ItemKind::Chest => Some(Container::default()),
+            _ => None
+        };
+        let charges = match kind {
+            ItemKind::Wand(spell) => Some(Attribute::from(spell.charges())),
+            _ => None
+        };
+        let durability = match kind {
+            ItemKind::Key(_) => Some(Attribute::from(KEY_DURABILITY)),
+            ItemKind::Hammer |
+            ItemKind::Pickaxe |
+            ItemKind::Axe => Some(Attribute::from(TOOL_DURABILITY)),
+            _ => None
+        };
+
         Self {
             kind,
             pos: None,
-            owner: None
+            owner: None,
+            container,
+            quantity: 1,
+            charges,
+            durability
         }
     }
 
+    /// Set the size of the stack. Has no effect if the item is not
+    /// stackable.
+    pub fn with_quantity(mut self, quantity: u16) -> Self {
+        if self.is_stackable() {
+            self.quantity = quantity;
+        }
+        self
+    }
+
+    pub fn is_stackable(&self) -> bool {
+        match self.kind {
+            ItemKind::Money |
+            ItemKind::Ore |
+            ItemKind::Gold |
+            ItemKind::Potion(_) |
+            ItemKind::Bread |
+            ItemKind::Planks => true,
+            _ => false
+        }
+    }
+
+    /// Return true if both items can be merged into a single stack.
+    pub fn stacks_with(&self, other: &Item) -> bool {
+        self.is_stackable() && self.kind == other.kind
+    }
+
     pub fn with_pos<P>(mut self, pos: P) -> Self
     where P: Into<Point>
     {
@@ -69,48 +253,259 @@ impl Item {
         self
     }
 
+    pub fn with_charges(mut self, charges: u16) -> Self {
+        self.charges = Some(Attribute::from(charges));
+        self
+    }
+
+    pub fn with_durability(mut self, durability: u16) -> Self {
+        self.durability = Some(Attribute::from(durability));
+        self
+    }
+
+    /// Return the remaining charges or durability as a fraction of
+    /// the maximum, if the item has any.
+    pub fn condition(&self) -> Option<f32> {
+        self.charges.as_ref().or(self.durability.as_ref())
+            .map(|attribute| attribute.value as f32 / attribute.max.max(1) as f32)
+    }
+
     pub fn with_owner(mut self, owner: ActorId) -> Self {
         self.owner = Some(owner);
         self
     }
 
+    /// Put the given items into the container. Has no effect if
+    /// the item is not a container.
+    pub fn with_content(mut self, items: Inventory) -> Self {
+        if let Some(container) = self.container.as_mut() {
+            container.items = items;
+        }
+        self
+    }
+
+    /// Lock the container with the given lock id. Has no effect if
+    /// the item is not a container.
+    pub fn with_lock(mut self, lock: LockId) -> Self {
+        if let Some(container) = self.container.as_mut() {
+            container.lock = Some(lock);
+        }
+        self
+    }
+
+    pub fn with_faction(mut self, faction: Faction) -> Self {
+        if let Some(container) = self.container.as_mut() {
+            container.faction = Some(faction);
+        }
+        self
+    }
+
+    /// Return true if the item has charges left, e.g. a wand that
+    /// can still be zapped.
+    pub fn has_charges(&self) -> bool {
+        self.charges.as_ref().map_or(false, |charges| charges.value > 0)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        matches!(self.container, Some(Container { lock: Some(_), .. }))
+    }
+
     pub fn description(&self) -> String {
+        let description = match (&self.kind, &self.charges) {
+            (ItemKind::Money, _) => return format!("{} coins of gold", self.quantity),
+            (ItemKind::Wand(_), Some(charges)) => format!("{} ({} charges)", self.kind.description(), charges.value),
+            _ => self.kind.description()
+        };
+
+        let description = match &self.durability {
+            Some(durability) if 4 * durability.value <= durability.max => format!("{} (almost broken)", description),
+            Some(durability) if durability.value < durability.max => format!("{} (worn)", description),
+            _ => description
+        };
+
+        if self.quantity > 1 {
+            format!("{} ({})", description, self.quantity)
+        } else {
+            description
+        }
+    }
+
+    /// Return the weight of a single item of the stack.
+    pub fn unit_weight(&self) -> Weight {
         match self.kind {
-            ItemKind::Wand => String::from("a magical wand"),
-            ItemKind::Ore => format!("a chunk of ore"),
-            ItemKind::Gold => format!("a chunk of gold"),
-            ItemKind::Bread => format!("a loaf of bread"),
-            ItemKind::Money(x) => format!("{} coins of gold", x),
-            ItemKind::Potion(Potion::Empty) => format!("an empty potion"),
-            ItemKind::Potion(Potion::Healing) => format!("a potion of healing"),
-            ItemKind::Potion(Potion::Swimming) => format!("a potion of swimming"),
-            ItemKind::Potion(Potion::Vision) => format!("a potion of vision"),
-            ItemKind::Barrel => format!("a wooden barrel"),
-            ItemKind::Key => format!("a metal key"),
+            ItemKind::Money => 0,
+            ItemKind::Wand(_) => 1,
+            ItemKind::Ore => 5,
+            ItemKind::Gold => 5,
+            ItemKind::Potion(Potion::Empty) => 1,
+            ItemKind::Potion(_) => 2,
+            ItemKind::Bread => 1,
+            ItemKind::Barrel => 50,
+            ItemKind::Chest => 80,
+            ItemKind::Key(_) => 1,
+            ItemKind::Sword => 6,
+            ItemKind::Shield => 8,
+            ItemKind::Armour => 15,
+            ItemKind::Helmet => 4,
+            ItemKind::ClimbingBoots => 3,
+            ItemKind::RingOfVision => 0,
+            ItemKind::Hammer => 4,
+            ItemKind::Torch => 2,
+            ItemKind::Lantern => 3,
+            ItemKind::Pickaxe => 6,
+            ItemKind::Axe => 5,
+            ItemKind::Planks => 8,
+        }
+    }
+
+    /// Return the value (in coins) of a single item of the stack. Items
+    /// without a value cannot be traded.
+    pub fn value(&self) -> u16 {
+        match self.kind {
+            ItemKind::Money => 0,
+            ItemKind::Wand(_) => 30,
+            ItemKind::Ore => 3,
+            ItemKind::Gold => 20,
+            ItemKind::Potion(Potion::Empty) => 1,
+            ItemKind::Potion(Potion::Healing) => 8,
+            ItemKind::Potion(Potion::Vision) => 12,
+            ItemKind::Potion(Potion::Swimming) => 15,
+            ItemKind::Potion(Potion::Climbing) => 15,
+            ItemKind::Potion(Potion::Water) => 1,
+            ItemKind::Bread => 2,
+            ItemKind::Barrel => 0,
+            ItemKind::Chest => 0,
+            ItemKind::Key(_) => 0,
+            ItemKind::Sword => 25,
+            ItemKind::Shield => 15,
+            ItemKind::Armour => 30,
+            ItemKind::Helmet => 12,
+            ItemKind::ClimbingBoots => 20,
+            ItemKind::RingOfVision => 40,
+            ItemKind::Hammer => 10,
+            ItemKind::Torch => 2,
+            ItemKind::Lantern => 15,
+            ItemKind::Pickaxe => 12,
+            ItemKind::Axe => 10,
+            ItemKind::Planks => 3,
+        }
+    }
+
+    /// Return the weight of the whole stack.
+    pub fn weight(&self) -> Weight {
+        self.unit_weight() * self.quantity as Weight
+    }
+
+    /// Return the slot in which the item can be worn or wielded.
+    pub fn equipment_slot(&self) -> Option<EquipmentSlot> {
+        match self.kind {
+            ItemKind::Sword => Some(EquipmentSlot::Weapon),
+            ItemKind::Shield => Some(EquipmentSlot::OffHand),
+            ItemKind::Armour => Some(EquipmentSlot::Body),
+            ItemKind::Helmet => Some(EquipmentSlot::Head),
+            ItemKind::ClimbingBoots => Some(EquipmentSlot::Feet),
+            ItemKind::RingOfVision => Some(EquipmentSlot::Ring),
+            _ => None
+        }
+    }
+
+    /// Return the bonus that the item grants while it is equipped.
+    pub fn stat_modifier(&self) -> StatModifier {
+        match self.kind {
+            ItemKind::Sword => StatModifier::attack(3),
+            ItemKind::Shield => StatModifier::defense(2),
+            ItemKind::Armour => StatModifier::defense(2),
+            ItemKind::Helmet => StatModifier::defense(1),
+            ItemKind::ClimbingBoots => StatModifier::skill(SkillKind::Climb),
+            ItemKind::RingOfVision => StatModifier::skill(SkillKind::Vision { radius: 2 }),
+            _ => StatModifier::default()
+        }
+    }
+
+    /// Return the nutrition that eating the item restores, or None
+    /// if the item cannot be eaten.
+    pub fn nutrition(&self) -> Option<u16> {
+        match self.kind {
+            ItemKind::Bread => Some(MAX_NUTRITION / 3),
+            _ => None
+        }
+    }
+
+    /// Return the light that the item sheds, whether it is carried
+    /// or lying on the ground.
+    pub fn light_source(&self) -> Option<LightSource> {
+        match self.kind {
+            ItemKind::Torch => Some(LightSource::TORCH),
+            ItemKind::Lantern => Some(LightSource::LANTERN),
+            _ => None
         }
     }
 
-    // defined as function so that we can alter the item
-    pub fn use_item_on(world: &mut World, item_id: &ItemId, target: &ActorId, pos: &Point) {
+    /// Return true if the item is ruined when it gets soaked, e.g.
+    /// while swimming in deep water.
+    pub fn is_ruined_by_water(&self) -> bool {
+        matches!(self.kind, ItemKind::Bread | ItemKind::Torch)
+    }
+
+    // defined as function so that we can alter the item. Most effects
+    // are applied right away, only the wear of the item is left to
+    // the caller.
+    pub fn use_item_on(world: &mut World, item_id: &ItemId, target: &ActorId, pos: &Point) -> UseResult {
+        let actor_id = *target;
         if let Some(item) = world.items.get(&item_id) {
             if let Some(target) = world.actors.get(&target) {
                 match item.kind {
-                    ItemKind::Key => {
+                    ItemKind::Key(key_lock) => {
+                        // a locked container at the position takes precedence
+                        let container_id = world.item_ids_at(&pos).into_iter()
+                            .find(|id| world.items.get(id).map_or(false, |item| item.is_locked()));
+                        if let Some(container_id) = container_id {
+                            let container = world.items.get_mut(&container_id)
+                                .and_then(|item| item.container.as_mut()).unwrap();
+                            if container.lock == Some(key_lock) {
+                                container.lock = None;
+                                world.messages.push(format!("You unlock the container."));
+                                world.events.emit(GameEvent::ContainerUnlocked { actor_id, container_id, lock: key_lock });
+                                return UseResult::Wear;
+                            } else {
+                                world.messages.push(format!("The key does not fit into this lock."));
+                            }
+                            return UseResult::Cancel;
+                        }
+
+                        let mut result = UseResult::Cancel;
                         if let Some(terrain) = world.terrain.get_mut(&pos) {
-                            if matches!(terrain.kind, TerrainKind::Door(_)) {
-                                if terrain.kind == TerrainKind::Door(DoorState::Open) {
-                                    world.messages.push(format!("The door is already open, no need to unlock anything."));
-                                } else {
+                            match terrain.kind {
+                                TerrainKind::Door(DoorState::Locked(door_lock)) if door_lock == key_lock => {
                                     terrain.kind = TerrainKind::Door(DoorState::Open);
                                     world.messages.push(format!("You unlock the door"));
-                                }
+                                    world.events.emit(GameEvent::DoorUnlocked { actor_id, pos: *pos, lock: key_lock });
+                                    result = UseResult::Wear;
+                                },
+                                TerrainKind::Door(DoorState::Locked(_)) => {
+                                    world.messages.push(format!("The key does not fit into this lock."));
+                                },
+                                TerrainKind::Door(_) => {
+                                    world.messages.push(format!("The door is not locked, no need to unlock anything."));
+                                },
+                                _ => {}
                             }
                         }
+                        // the door might have been opened, revealing what is behind
+                        world.update_fov(&world.player_id());
+                        return result;
                     },
                     ItemKind::Potion(Potion::Empty) => {
+                        let owner = item.owner;
                         if let Some(terrain) = world.terrain.get(&pos) {
                             if terrain.feature == Some(TerrainFeature::Fountain) {
-                                world.items.replace(&item_id, Item::new(ItemKind::Potion(Potion::Healing)));
+                                // only a single bottle of the stack is filled up
+                                world.take_one(&item_id);
+                                let potion = Item::new(ItemKind::Potion(Potion::Healing));
+                                match owner {
+                                    Some(owner_id) => { world.give_item(&owner_id, potion); },
+                                    None => { world.items.add(potion.with_pos(*pos)); }
+                                }
                                 world.messages.push(format!("You fill up the empty bottle with the healing water."));
                             } else {
                                 world.messages.push(format!("Nothing to fill up the bottle..."));
@@ -119,10 +514,55 @@ impl Item {
                             world.messages.push(format!("Nothing here!"));
                         }
                     }
+                    ItemKind::Bread => {
+                        let animal_id = world.actor_id_at(&pos)
+                            .filter(|id| world.actors.get(id).map_or(false, |actor| actor.is_animal()));
+                        match animal_id {
+                            // selecting one's own position means eating the bread
+                            None if *pos == target.pos => {
+                                let item = item.clone();
+                                if world.eat(&actor_id, &item) {
+                                    world.take_one(&item_id);
+                                    world.events.emit(GameEvent::ItemUsed { actor_id, item_id: *item_id, kind: item.kind });
+                                }
+                            },
+                            Some(animal_id) => {
+                                let description = world.actors.get(&animal_id).unwrap().description();
+                                world.messages.push(format!("You feed {} with some bread.", description));
+                                world.take_one(&item_id);
+                                world.tame(&animal_id, &actor_id);
+                            },
+                            None => world.messages.push("There is no one to feed.")
+                        }
+                    },
+                    ItemKind::Wand(spell) => {
+                        if !item.has_charges() {
+                            world.messages.push("You wave the wand, but nothing happens.");
+                            return UseResult::Cancel;
+                        }
+                        let kind = item.kind.clone();
+                        world.events.emit(GameEvent::ItemUsed { actor_id, item_id: *item_id, kind: kind.clone() });
+                        spell::cast(world, &actor_id, spell, *pos);
+                        if actor_id == world.player_id() {
+                            world.identify(&kind);
+                        }
+                        return UseResult::Discharge;
+                    },
+                    ItemKind::Pickaxe | ItemKind::Axe | ItemKind::Torch | ItemKind::Planks => {
+                        let alteration = Alteration::of_tool(&item.kind).unwrap();
+                        if world.alter(&actor_id, pos, alteration) {
+                            if alteration.consumes_tool() {
+                                return UseResult::UsedUp;
+                            } else if alteration.wears_tool() {
+                                return UseResult::Wear;
+                            }
+                        }
+                    },
                     _ => {}
                 }
             }
         }
+        UseResult::Cancel
     }
     
     pub fn use_item(&mut self, world: &mut World, target: &ActorId) -> UseResult {
@@ -172,28 +612,80 @@ impl Item {
                 self.kind = ItemKind::Potion(Potion::Empty);
                 UseResult::Replace
             },
-            ItemKind::Key => {
-                // the player can try to open any door which is around her
-                // TODO: extend to objects that could be unlocked such as chests
-                let offsets = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+            ItemKind::Potion(Potion::Water) => {
+                world.messages.push("You drink the water. How refreshing!");
+                self.kind = ItemKind::Potion(Potion::Empty);
+                UseResult::Replace
+            },
+            ItemKind::Potion(Potion::Climbing) => {
+                let actor = world.actors.get_mut(target).unwrap();
+                actor.skills.push(Skill::new(SkillKind::Climb));
+                world.messages.push((MessageKind::Skill, "You drink the potion and your hands feel sticky."));
+                self.kind = ItemKind::Potion(Potion::Empty);
+                UseResult::Replace
+            },
+            ItemKind::Key(_) => {
+                // the player can try to open any door or container which
+                // is around her, but the key only fits if the lock ids match
+                let offsets = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 0), (0, 1), (1, -1), (1, 0), (1, 1)];
                 if let Some(player) = world.actors.get(&world.player_id()) {
-                    let doors = offsets.iter()
+                    let is_locked_door = |pos: &Point| world.terrain.get(pos)
+                        .map_or(false, |terrain| matches!(terrain.kind, TerrainKind::Door(DoorState::Locked(_))));
+                    let has_locked_container = |pos: &Point| world.item_ids_at(pos).iter()
+                        .any(|id| world.items.get(id).map_or(false, |item| item.is_locked()));
+                    let locks = offsets.iter()
                         .map(|offset| player.pos.offset(offset.0, offset.1))
-                        .map(|pos| (pos, world.terrain.get(&pos)))
-                        .filter(|(pos, terrain)| terrain.is_some())
-                        .filter(|(pos, terrain)| matches!(terrain.unwrap().kind, TerrainKind::Door(DoorState::Locked)))
-                        .map(|(pos, _)| pos)
+                        .filter(|pos| is_locked_door(pos) || has_locked_container(pos))
                         .collect::<HashSet<Point>>();
 
-                    if doors.len() > 0 {
-                        println!("use key: switching to Select mode with {} positions", doors.len());
-                        return UseResult::Select { positions: doors };
+                    if locks.len() > 0 {
+                        println!("use key: switching to Select mode with {} positions", locks.len());
+                        return UseResult::Select { positions: locks };
                     } else {
                         world.messages.push((MessageKind::Info, "There is nothing to unlock around you"));
                     }
                 }
                 return UseResult::Cancel;
-            }
+            },
+            ItemKind::Bread => {
+                // bread can be eaten or fed to the animals around the player
+                if let Some(player) = world.actors.get(&world.player_id()) {
+                    let mut animals = world.actors.iter()
+                        .filter(|(_, actor)| actor.is_animal())
+                        .filter(|(_, actor)| (actor.pos.x - player.pos.x).abs() <= 1
+                                && (actor.pos.y - player.pos.y).abs() <= 1)
+                        .map(|(_, actor)| actor.pos)
+                        .collect::<HashSet<Point>>();
+
+                    if animals.len() > 0 {
+                        // the player's own position stands for eating
+                        animals.insert(player.pos);
+                        return UseResult::Select { positions: animals };
+                    }
+                }
+                if world.eat(target, self) {
+                    UseResult::UsedUp
+                } else {
+                    UseResult::Cancel
+                }
+            },
+            ItemKind::Pickaxe | ItemKind::Axe | ItemKind::Torch | ItemKind::Planks => {
+                // the tool works on the matching terrain around the player
+                let alteration = Alteration::of_tool(&self.kind).unwrap();
+                let positions = world.alterable_positions(target, alteration);
+                if positions.len() > 0 {
+                    return UseResult::Select { positions };
+                }
+                world.messages.push((MessageKind::Info, alteration.nothing_to_do()));
+                UseResult::Cancel
+            },
+            ItemKind::Wand(_) => {
+                if !self.has_charges() {
+                    world.messages.push((MessageKind::Info, "The wand has no charges left."));
+                    return UseResult::Cancel;
+                }
+                UseResult::Target
+            },
             _ => UseResult::Cancel
         }
     }
@@ -201,19 +693,35 @@ impl Item {
 
 pub fn item_index(item: &Item) -> usize {
     match item.kind {
+        ItemKind::Money if item.quantity > 50 => 13,
+        ItemKind::Money if item.quantity >= 30 => 12,
+        ItemKind::Money if item.quantity >= 10 => 11,
+        ItemKind::Money => 10,
         ItemKind::Ore => 0,
         ItemKind::Gold => 1,
-        ItemKind::Wand => 2,
+        ItemKind::Wand(_) => 2,
         ItemKind::Bread => 3,
-        ItemKind::Money(x) if x > 50 => 13,
-        ItemKind::Money(x) if x < 50 => 12,
-        ItemKind::Money(x) if x < 30 => 11,
-        ItemKind::Money(_) => 10,
         ItemKind::Barrel => 20,
+        ItemKind::Chest => 20, // TODO: there is no tile for a chest yet
         ItemKind::Potion(Potion::Healing) => 71,
         ItemKind::Potion(Potion::Swimming) => 72,
         ItemKind::Potion(Potion::Vision) => 73,
+        ItemKind::Potion(Potion::Climbing) => 74,
+        ItemKind::Potion(Potion::Water) => 70,
         ItemKind::Potion(Potion::Empty) => 75,
-        ItemKind::Key => 91,
+        ItemKind::Key(_) => 91,
+        ItemKind::Sword => 80,
+        ItemKind::RingOfVision => 93,
+        // TODO: there are no tiles for the following items yet
+        ItemKind::Shield => 64,
+        ItemKind::Armour => 64,
+        ItemKind::Helmet => 64,
+        ItemKind::ClimbingBoots => 64,
+        ItemKind::Hammer => 64,
+        ItemKind::Torch => 64,
+        ItemKind::Lantern => 64,
+        ItemKind::Pickaxe => 64,
+        ItemKind::Axe => 64,
+        ItemKind::Planks => 64,
     }
 }
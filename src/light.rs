@@ -0,0 +1,165 @@
+//! Light sources, day and night.
+//!
+//! The daylight follows the time of day: the sky is bright from
+//! morning to evening and dark at night, with dawn and dusk in
+//! between. Light sources, e.g. a carried torch or a glowing
+//! fountain, light up the tiles around them. Tiles that are too dark
+//! cannot be seen, see `World::update_fov`.
+//!
+//! The LightMap is recomputed by `World::update_lights`.
+//!
+
+use crate::{
+    point::Point,
+    terrain::TerrainMap,
+    schedule::{TURNS_PER_HOUR, HOURS_PER_DAY}
+};
+
+use std::collections::HashMap;
+
+/// Light level of the sky at night.
+pub const NIGHT_LIGHT: f32 = 0.1;
+/// Tiles with a lower light level cannot be seen.
+pub const MIN_VISIBLE_LIGHT: f32 = 0.2;
+
+/// Red, green and blue components of a light colour.
+pub type Tint = (f32, f32, f32);
+
+pub const WHITE_LIGHT: Tint = (1.0, 1.0, 1.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub radius: i32,
+    pub colour: Tint
+}
+
+impl LightSource {
+    pub const TORCH: LightSource = LightSource { radius: 4, colour: (1.0, 0.75, 0.45) };
+    pub const LANTERN: LightSource = LightSource { radius: 6, colour: (1.0, 0.9, 0.7) };
+    pub const FOUNTAIN: LightSource = LightSource { radius: 2, colour: (0.6, 0.8, 1.0) };
+    pub const WINDOW: LightSource = LightSource { radius: 1, colour: (1.0, 0.85, 0.5) };
+}
+
+/// Light that falls onto a tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    /// between 0 (dark) and 1 (bright)
+    pub level: f32,
+    pub colour: Tint
+}
+
+/// Return the light level of the sky at the given time.
+pub fn daylight(time: i32) -> f32 {
+    let hour = time.rem_euclid(TURNS_PER_HOUR * HOURS_PER_DAY) as f32 / TURNS_PER_HOUR as f32;
+    let fade = |t: f32| NIGHT_LIGHT + (1.0 - NIGHT_LIGHT) * t;
+    match hour {
+        h if h < 5.0 => NIGHT_LIGHT,
+        // dawn
+        h if h < 7.0 => fade((h - 5.0) / 2.0),
+        h if h < 19.0 => 1.0,
+        // dusk
+        h if h < 21.0 => fade((21.0 - h) / 2.0),
+        _ => NIGHT_LIGHT
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LightMap {
+    /// light level of the sky
+    pub daylight: f32,
+    /// tiles that are lit by light sources
+    lit: HashMap<Point, Light>
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        LightMap::new(1.0)
+    }
+}
+
+impl LightMap {
+    pub fn new(daylight: f32) -> Self {
+        Self {
+            daylight,
+            lit: HashMap::new()
+        }
+    }
+
+    /// Return true if the sky is too dark to see anything.
+    pub fn is_dark(&self) -> bool {
+        self.daylight < MIN_VISIBLE_LIGHT
+    }
+
+    /// Light up the tiles around the source. The light fades with
+    /// the distance and does not pass opaque terrain.
+    pub fn add_source(&mut self, source: &LightSource, pos: Point, terrain: &TerrainMap) {
+        let radius = source.radius;
+        for x in -radius..=radius {
+            for y in -radius..=radius {
+                let target = pos.offset(x, y);
+                let distance = ((x * x + y * y) as f32).sqrt();
+                if distance > radius as f32 {
+                    continue;
+                }
+                let is_hidden = pos.line_to(&target)
+                    .filter(|&q| q != pos && q != target)
+                    .any(|q| terrain.get(&q).map_or(false, |tile| tile.blocks_sight()));
+                if is_hidden {
+                    continue;
+                }
+
+                let level = 1.0 - distance / (radius + 1) as f32;
+                let brighter = self.lit.get(&target).map_or(true, |light| level > light.level);
+                if brighter {
+                    self.lit.insert(target, Light { level, colour: source.colour });
+                }
+            }
+        }
+    }
+
+    /// Return the light at the given position, which is the light of
+    /// the brightest source or the daylight.
+    pub fn light_at(&self, pos: &Point) -> Light {
+        match self.lit.get(pos) {
+            Some(light) if light.level > self.daylight => *light,
+            _ => Light { level: self.daylight, colour: WHITE_LIGHT }
+        }
+    }
+
+    /// Return true if there is enough light to see the tile.
+    pub fn is_visible(&self, pos: &Point) -> bool {
+        self.light_at(pos).level >= MIN_VISIBLE_LIGHT
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::{Terrain, TerrainKind};
+
+    #[test]
+    fn day_and_night() {
+        let hour = |h: i32| h * TURNS_PER_HOUR;
+        assert_eq!(daylight(hour(12)), 1.0);
+        assert_eq!(daylight(hour(0)), NIGHT_LIGHT);
+        assert_eq!(daylight(hour(24 + 23)), NIGHT_LIGHT);
+        let dawn = daylight(hour(6));
+        assert!(NIGHT_LIGHT < dawn && dawn < 1.0);
+    }
+
+    #[test]
+    fn light_fades_and_stops_at_walls() {
+        let mut terrain = TerrainMap::new();
+        let wall = Point::new(2, 0);
+        terrain.insert(wall, Terrain::from(TerrainKind::Wall));
+        let mut light = LightMap::new(NIGHT_LIGHT);
+        light.add_source(&LightSource::TORCH, Point::new(0, 0), &terrain);
+
+        assert!(light.is_dark());
+        assert!(light.light_at(&Point::new(0, 0)).level > light.light_at(&Point::new(0, 2)).level);
+        assert!(light.is_visible(&wall));
+        assert!(!light.is_visible(&Point::new(3, 0)));
+        assert!(!light.is_visible(&Point::new(0, 5)));
+    }
+}
@@ -1,30 +1,47 @@
 use macroquad::prelude::*;
 
 mod action;
+mod alteration;
+mod ai;
 mod actor;
 mod demo_game;
+mod craft;
+mod dialogue;
+mod equipment;
+mod event;
+mod experience;
+mod faction;
 mod flake;
+mod identify;
 mod idmap;
 mod item;
+mod light;
 mod message;
+mod path;
 mod pattern;
 mod point;
+mod quest;
 mod skill;
+mod spell;
 mod render;
+mod schedule;
 mod terrain;
+mod trade;
+mod trap;
+mod weather;
 mod world;
 
 extern crate rand;
-use rand::Rng;
 
 use action::{Action, GuiAction};
-use actor::{Inventory, ActorAI, ActorKind};
-use item::{Item, ItemId, ItemKind};
+use actor::{Inventory, ActorKind, ActorId, CompanionCommand};
+use item::{ItemId, ItemKind};
 use message::{Message, MessageKind, MessageQueue};
 use pattern::Pattern;
 use point::{Point, Rectangle, PointSet};
 use render::{Map, Tileset, Layer, InventoryWidget, egui };
 use world::{World, ViewportMode, adjust_viewport, HighlightMode, RenderMode};
+use terrain::{TerrainKind, DoorState};
 
 use std::collections::HashSet;
 
@@ -247,6 +264,68 @@ fn read_input_default(state: &MainState, world: &World) -> Vec<Action> {
         }
     }
 
+    // E => equip item
+    if is_key_pressed(KeyCode::E) {
+        println!("switching to equip item mode");
+
+        // only offer items which can be worn or wielded
+        let inventory = world.actors.get(&world.player_id()).unwrap().inventory.iter()
+            .filter(|id| world.items.get(id).map_or(false, |item| item.equipment_slot().is_some()))
+            .cloned()
+            .collect::<Inventory>();
+        if inventory.len() > 0 {
+            let pattern = &Pattern::MatrixWithGaps {
+                rows: 1, cols: inventory.len() as u16,
+                width: 48.0, height: 48.0,
+                sep_x: 2.0, sep_y: 2.0
+            };
+            let mut widget = InventoryWidget::new(vec2(0.0, 0.0), &pattern, false, state.params_slots.clone());
+
+            let pos = world.player_pos();
+            let map_pos = pos - state.viewport.top_left();
+            if let Some(screen_pos) = state.main_map.tile_to_screen(&map_pos) {
+                // TODO: adding the base here is a mess and will eventually
+                // lead to an error. We should consider putting the offset
+                // somewhere, where it is automatically used.
+                let screen_pos = screen_pos + state.main_map_pos;
+                // offset a little (height of a single inventory item),
+                // so that the inventory is above the main player
+                let screen_pos = screen_pos - vec2(0.0, 48.0);
+                widget.set_pos(&screen_pos);
+
+                actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::EquipItem { inventory, widget, hover: None } )));
+            }
+        } else {
+            actions.push(Action::DisplayMessage { msg: "You carry nothing that you could equip.".into() });
+        }
+    }
+
+    // G => hide/show equipment
+    if is_key_pressed(KeyCode::G) {
+        actions.push(Action::GUI(GuiAction::HideShowEquipment));
+    }
+
+    // J => hide/show quest journal
+    if is_key_pressed(KeyCode::J) {
+        actions.push(Action::GUI(GuiAction::HideShowJournal));
+    }
+
+    // R => hide/show crafting recipes
+    if is_key_pressed(KeyCode::R) {
+        actions.push(Action::GUI(GuiAction::HideShowCrafting));
+    }
+
+    // L => look for hidden traps and doors
+    if is_key_pressed(KeyCode::L) {
+        actions.push(Action::EndTurn);
+        actions.push(Action::Search { actor_id: world.player_id() });
+    }
+
+    // X => hide/show character sheet
+    if is_key_pressed(KeyCode::X) {
+        actions.push(Action::GUI(GuiAction::HideShowCharacter));
+    }
+
     // D => drop item
     if is_key_pressed(KeyCode::D) {
         println!("switching to drop item mode");
@@ -350,6 +429,44 @@ fn read_input_default(state: &MainState, world: &World) -> Vec<Action> {
             }
         }
     }
+
+    // O => Open container
+    if is_key_pressed(KeyCode::O) {
+        // the player can open any container which is around her
+        // or on her own position
+        if let Some(player) = world.actors.get(&world.player_id()) {
+            let positions = world.items.values()
+                .filter(|item| item.container.is_some())
+                .filter_map(|item| item.pos)
+                .filter(|pos| (pos.x - player.pos.x).abs() <= 1 && (pos.y - player.pos.y).abs() <= 1)
+                .collect::<PointSet>();
+            if positions.len() > 0 {
+                println!("opening container: switching to SelectMode with {} positions", positions.len());
+                actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::SelectOpen { positions })));
+            } else {
+                actions.push(Action::DisplayMessage { msg: "There is nothing to open close to you.".into() });
+            }
+        }
+    }
+
+    // K => Close door
+    if is_key_pressed(KeyCode::K) {
+        // the player can close any open door which is around her
+        let offsets = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+        if let Some(player) = world.actors.get(&world.player_id()) {
+            let positions = offsets.iter()
+                .map(|offset| player.pos.offset(offset.0, offset.1))
+                .filter(|pos| matches!(world.terrain.get(pos).map(|t| &t.kind),
+                                       Some(TerrainKind::Door(DoorState::Open))))
+                .collect::<PointSet>();
+            if positions.len() > 0 {
+                println!("closing door: switching to SelectMode with {} positions", positions.len());
+                actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::SelectClose { positions })));
+            } else {
+                actions.push(Action::DisplayMessage { msg: "There is no open door close to you.".into() });
+            }
+        }
+    }
     
     actions
 }
@@ -364,6 +481,10 @@ pub struct MainState {
     show_help: bool,
     show_status: bool,
     show_messages: bool,
+    show_equipment: bool,
+    show_journal: bool,
+    show_crafting: bool,
+    show_character: bool,
     viewport: Rectangle,
     border_size: Point,
     egui_has_focus: bool,
@@ -389,8 +510,22 @@ pub enum InputMode {
     UseItem { inventory: Inventory, widget: InventoryWidget, hover: Option<ItemId> },
     PickUpItem { inventory: Inventory, widget: InventoryWidget, hover: Option<ItemId> },
     DropItem { inventory: Inventory, widget: InventoryWidget, hover: Option<ItemId> },
+    EquipItem { inventory: Inventory, widget: InventoryWidget, hover: Option<ItemId> },
+    DropAmount { item_id: ItemId, amount: u16, max: u16 },
+    Trade { shopkeeper_id: ActorId },
+    Dialogue { actor_id: ActorId, node: String },
     SelectTalk { positions: PointSet }, // assume that one talking partner is always the player itself
-    SelectUse { positions: PointSet, item_id: ItemId }
+    SelectUse { positions: PointSet, item_id: ItemId },
+    /// aim a projectile, e.g. of a wand, at the cursor position
+    Target { item_id: ItemId, cursor: Point },
+    SelectClose { positions: PointSet },
+    SelectOpen { positions: PointSet },
+    Container {
+        container_id: ItemId,
+        inventory: Inventory, widget: InventoryWidget,
+        content: Inventory, content_widget: InventoryWidget,
+        hover: Option<ItemId>
+    }
 }
 
 impl MainState {
@@ -431,6 +566,7 @@ impl MainState {
         main_map.add_layer(Layer::Actor {
             tileset: Tileset::new("assets/actors32.png", &pattern).await.unwrap()
         });
+        main_map.add_layer(Layer::Weather);
 
         main_map.add_layer(Layer::Highlight);
 
@@ -468,6 +604,10 @@ impl MainState {
             show_help: false,
             show_status: false,
             show_messages: false,
+            show_equipment: false,
+            show_journal: false,
+            show_crafting: false,
+            show_character: false,
             item_tileset,
             viewport,
             border_size: Point::from((10, 10)),
@@ -503,6 +643,21 @@ impl MainState {
                         *hover = None;
                     }
                 },                    
+            InputMode::EquipItem { inventory, widget, hover } =>
+                match read_input_from_inventory(&widget, &inventory, &world) {
+                    InventorySelection::Cancel => actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default))),
+                    InventorySelection::Item { item_id } => {
+                        actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                        actions.push(Action::EndTurn);
+                        actions.push(Action::Equip { actor_id: world.player_id(), item_id });
+                    },
+                    InventorySelection::Hover { item_id } => {
+                        hover.replace(item_id);
+                    },
+                    InventorySelection::None => {
+                        *hover = None;
+                    }
+                },
             InputMode::PickUpItem { inventory, widget, hover } =>
                 match read_input_from_inventory(&widget, &inventory, &world) {
                     InventorySelection::Cancel => actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default))),
@@ -532,6 +687,16 @@ impl MainState {
                 match read_input_from_inventory(&widget, &inventory, &world) {
                     InventorySelection::Cancel => actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default))),
                     InventorySelection::Item { item_id } => {
+                        // ask for the number of items to drop if the
+                        // selected item is a stack
+                        let quantity = world.items.get(&item_id).map_or(1, |item| item.quantity);
+                        if quantity > 1 {
+                            actions.push(Action::GUI(GuiAction::SwitchMode(
+                                InputMode::DropAmount { item_id, amount: quantity, max: quantity }
+                            )));
+                            return;
+                        }
+
                         world.drop_item(&item_id);
                         *inventory = inventory.iter()
                             .filter(|&id| id != &item_id)
@@ -549,6 +714,38 @@ impl MainState {
                         *hover = None;
                     }
                 },
+            InputMode::DropAmount { item_id, amount, max } => {
+                if is_key_pressed(KeyCode::Escape) {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                }
+
+                if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Right) {
+                    *amount = (*amount + 1).min(*max);
+                }
+
+                if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::Left) {
+                    *amount = (*amount - 1).max(1);
+                }
+
+                if is_key_pressed(KeyCode::Enter) {
+                    if let Some(stack_id) = world.split_stack(&item_id, *amount) {
+                        world.drop_item(&stack_id);
+                    }
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                }
+            },
+            InputMode::Trade { .. } => {
+                // buying and selling is done in the egui trade window
+                if is_key_pressed(KeyCode::Escape) {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                }
+            },
+            InputMode::Dialogue { .. } => {
+                // choices are made in the egui dialogue window
+                if is_key_pressed(KeyCode::Escape) {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                }
+            },
             InputMode::SelectTalk { positions } => {
                 if is_key_pressed(KeyCode::Escape) {
                     actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
@@ -564,7 +761,31 @@ impl MainState {
                             if let Some(actor_id) = world.actor_id_at(&map_pos) {
                                 println!("Hit position {:?} => {:?}", map_pos, actor_id);
                                 if let Some(actor) = world.actors.get(&actor_id) {
+                                    actions.push(Action::Talk { actor_id: world.player_id(), partner_id: actor_id });
+                                    // talking to a companion tells it to stay or to follow
+                                    if world.is_companion_of(&actor_id, &world.player_id()) {
+                                        let command = match actor.is_following() {
+                                            true => CompanionCommand::Stay,
+                                            false => CompanionCommand::Follow
+                                        };
+                                        actions.push(Action::CommandCompanion { companion_id: actor_id, command });
+                                        actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                                        return;
+                                    }
+                                    // actors with a dialogue start a conversation
+                                    let has_dialogue = actor.dialogue.as_ref()
+                                        .map_or(false, |name| world.dialogues.contains_key(name));
+                                    if has_dialogue {
+                                        let node = dialogue::START_NODE.into();
+                                        actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Dialogue { actor_id, node })));
+                                        return;
+                                    }
                                     actions.push(Action::DisplayMessage { msg: actor.quip().unwrap_or_else(|| format!("no answer...")).into() });
+                                    // talking to a shopkeeper opens the trade dialog
+                                    if actor.shop.is_some() && world.shop_is_open(&actor_id) {
+                                        actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Trade { shopkeeper_id: actor_id })));
+                                        return;
+                                    }
                                 };
                                 actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
                             }
@@ -584,12 +805,111 @@ impl MainState {
                         let map_pos = map_pos + self.viewport.top_left();
                         if positions.contains(&map_pos) {
                             println!("Selected position {:?}", map_pos);
-                            Item::use_item_on(world, &item_id, &world.player_id(), &map_pos);
+                            world.use_item_on(&item_id, &world.player_id(), &map_pos);
                             actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
                         }
                     };
                 }
             },            
+            InputMode::Target { item_id, cursor } => {
+                if is_key_pressed(KeyCode::Escape) {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                };
+
+                // the cursor is moved with the arrow keys or the mouse
+                for (key, dx, dy) in [(KeyCode::Left, -1, 0), (KeyCode::Right, 1, 0),
+                                      (KeyCode::Up, 0, -1), (KeyCode::Down, 0, 1)].iter() {
+                    if is_key_pressed(*key) {
+                        *cursor = cursor.offset(*dx, *dy);
+                    }
+                }
+
+                let mut fire = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space);
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    // TODO: use map offset, not arbitrary number
+                    let pos = Vec2::from(mouse_position()) - vec2(0.0, 32.0); // - map offset
+                    if let Some(map_pos) = self.main_map.screen_to_tile(&pos) {
+                        *cursor = map_pos + self.viewport.top_left();
+                        fire = true;
+                    }
+                }
+
+                if fire && *cursor != world.player_pos() {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                    actions.push(Action::EndTurn);
+                    actions.push(Action::Zap { actor_id: world.player_id(), item_id: *item_id, target: *cursor });
+                }
+            },
+            InputMode::SelectClose { positions } => {
+                if is_key_pressed(KeyCode::Escape) {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                };
+
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    // TODO: use map offset, not arbitrary number
+                    let pos = Vec2::from(mouse_position()) - vec2(0.0, 32.0); // - map offset
+                    if let Some(map_pos) = self.main_map.screen_to_tile(&pos) {
+                        let map_pos = map_pos + self.viewport.top_left();
+                        if positions.contains(&map_pos) {
+                            println!("Selected position {:?}", map_pos);
+                            actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                            actions.push(Action::EndTurn);
+                            actions.push(Action::CloseDoor { actor_id: world.player_id(), pos: map_pos });
+                        }
+                    };
+                }
+            },
+            InputMode::SelectOpen { positions } => {
+                if is_key_pressed(KeyCode::Escape) {
+                    actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                };
+
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    // TODO: use map offset, not arbitrary number
+                    let pos = Vec2::from(mouse_position()) - vec2(0.0, 32.0); // - map offset
+                    if let Some(map_pos) = self.main_map.screen_to_tile(&pos) {
+                        let map_pos = map_pos + self.viewport.top_left();
+                        if positions.contains(&map_pos) {
+                            println!("Selected position {:?}", map_pos);
+                            let container_id = world.item_ids_at(&map_pos).into_iter()
+                                .find(|id| world.items.get(id).map_or(false, |item| item.container.is_some()));
+                            actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                            if let Some(container_id) = container_id {
+                                actions.push(Action::OpenContainer { container_id });
+                            }
+                        }
+                    };
+                }
+            },
+            InputMode::Container { container_id, inventory, widget, content, content_widget, hover } => {
+                // items can be selected in both panes, the container
+                // content takes precedence
+                let selection = match read_input_from_inventory(&content_widget, &content, &world) {
+                    InventorySelection::None => read_input_from_inventory(&widget, &inventory, &world),
+                    selection => selection
+                };
+
+                match selection {
+                    InventorySelection::Cancel => actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default))),
+                    InventorySelection::Item { item_id } => {
+                        let actor_id = world.player_id();
+                        if content.contains(&item_id) {
+                            world.take_out(&actor_id, &container_id, &item_id);
+                        } else {
+                            world.put_into(&actor_id, &container_id, &item_id);
+                        }
+                        *inventory = world.actors.get(&actor_id).unwrap().inventory.clone();
+                        *content = world.container_content(&container_id);
+                        *hover = None;
+                    },
+                    InventorySelection::Hover { item_id } => {
+                        hover.replace(item_id);
+                    },
+                    InventorySelection::None => {
+                        *hover = None;
+                    }
+                }
+            },
 
         }
     }
@@ -606,7 +926,7 @@ impl MainState {
                 },
                 Action::EndTurn => {
                     self.end_of_turn = get_time();
-                    world.time += 1;
+                    world.end_turn();
                     // queue action for each NPC
                     for (id, actor) in world.actors.iter_mut()
                         .filter(|(_, actor)| actor.is_npc()) {
@@ -615,49 +935,48 @@ impl MainState {
                 },
                 Action::RunAI { actor_id } => {
                     let mut rng = rand::thread_rng();
-                    
-                    if let Some(npc) = world.actors.get(&actor_id) {
-                        match npc.ai {
-                            ActorAI::DoNothing => {},
-                            ActorAI::WanderAround => {
-                                if rng.gen::<f32>() > 0.3 {
-                                    let deltas = [(1,0), (0,1), (-1,0), (0,-1)];
-                                    let newpos: Vec<Point> = deltas.iter()
-                                        .map(|(x, y)| Point::from((*x as i32, *y as i32)))
-                                        .map(|delta| delta + npc.pos)
-                                        .filter(|newpos| !World::is_blocking(&newpos, &world.terrain, &world.actors))
-                                        .collect();
-
-                                    if newpos.len() > 0 {
-                                        let index = rng.gen_range(0..newpos.len());
-                                        actions.push(Action::Move { actor_id, pos: newpos[index] });
-                                    }
-                                }
-                            }
-                        }
+                    if let Some(action) = ai::run_ai(world, &actor_id, &mut rng) {
+                        actions.push(action);
                     }
                 },
+                Action::Attack { attacker_id, defender_id } => {
+                    world.melee_attack(&attacker_id, &defender_id);
+                },
+                Action::Craft { actor_id, recipe_id } => {
+                    world.craft(&actor_id, &recipe_id);
+                },
+                Action::Search { actor_id } => {
+                    world.search(&actor_id);
+                },
+                Action::Disarm { actor_id, pos } => {
+                    world.disarm(&actor_id, &pos, trap::DISARM_CHANCE);
+                },
+                Action::Zap { actor_id, item_id, target } => {
+                    world.zap(&actor_id, &item_id, target);
+                },
+                Action::CommandCompanion { companion_id, command } => {
+                    world.command_companion(&companion_id, command);
+                },
                 Action::Ouch => {
                     world.messages.push(Message::new(MessageKind::Info, "Ouch!", true));
                     self.end_of_turn = get_time();
                 },
                 Action::Move {actor_id, pos} => {
-                    if let Some(player) = world.actors.get_mut(&actor_id) {
-                        player.pos = pos;
-                        world.update_fov(&actor_id);
-                    }
+                    world.move_actor(&actor_id, pos);
                     // TODO: update map
                 },
                 Action::MoveFollow {actor_id, pos, mode} => {
-                    if let Some(player) = world.actors.get_mut(&actor_id) {
-                        player.pos = pos;
-                        adjust_viewport(
-                            &mut self.viewport,
-                            &self.border_size,
-                            &player.pos,
-                            mode
-                        );
-                        world.update_fov(&actor_id);
+                    world.move_actor(&actor_id, pos);
+                    // the move might have failed, e.g. a failed climb
+                    adjust_viewport(
+                        &mut self.viewport,
+                        &self.border_size,
+                        &world.player_pos(),
+                        mode
+                    );
+                    // carrying a heavy load makes each move take longer
+                    for _ in 0..world.encumbrance(&actor_id).extra_turns() {
+                        actions.push(Action::EndTurn);
                     }
                 },
                 Action::PickUp { actor_id, items } => {
@@ -672,6 +991,96 @@ impl MainState {
                     world.drop_item(&item_id);
                     self.input_mode = InputMode::Default;
                 }
+                Action::OpenDoor { actor_id, pos } => {
+                    world.open_door(&actor_id, &pos);
+                },
+                Action::CloseDoor { actor_id, pos } => {
+                    world.close_door(&actor_id, &pos);
+                },
+                Action::Buy { actor_id, shopkeeper_id, item_id } => {
+                    world.buy(&actor_id, &shopkeeper_id, &item_id);
+                },
+                Action::Sell { actor_id, shopkeeper_id, item_id } => {
+                    world.sell(&actor_id, &shopkeeper_id, &item_id);
+                },
+                Action::Talk { actor_id, partner_id } => {
+                    world.talk_to(&actor_id, &partner_id);
+                },
+                Action::DialogueChoice { actor_id, choice } => {
+                    let node = match &self.input_mode {
+                        InputMode::Dialogue { node, .. } => node.clone(),
+                        _ => continue
+                    };
+                    let choice = world.actors.get(&actor_id)
+                        .and_then(|actor| actor.dialogue.as_ref())
+                        .and_then(|name| world.dialogues.get(name))
+                        .and_then(|dialogue| dialogue.node(&node))
+                        .and_then(|node| node.choices.get(choice))
+                        .cloned();
+
+                    if let Some(choice) = choice {
+                        let player_id = world.player_id();
+                        let mut next_mode = match choice.target.as_str() {
+                            dialogue::END_NODE => InputMode::Default,
+                            target => InputMode::Dialogue { actor_id, node: target.into() }
+                        };
+                        for effect in &choice.effects {
+                            match effect {
+                                dialogue::Effect::OpenShop if world.shop_is_open(&actor_id) => {
+                                    next_mode = InputMode::Trade { shopkeeper_id: actor_id };
+                                },
+                                dialogue::Effect::OpenShop => {
+                                    world.messages.push("The shop is closed.");
+                                },
+                                effect => effect.apply(world, &player_id)
+                            }
+                        }
+                        self.input_mode = next_mode;
+                    }
+                },
+                Action::Equip { actor_id, item_id } => {
+                    world.equip(&actor_id, &item_id);
+                },
+                Action::Unequip { actor_id, slot } => {
+                    world.unequip(&actor_id, &slot);
+                },
+                Action::OpenContainer { container_id } => {
+                    if !world.open_container(&container_id) {
+                        continue;
+                    }
+
+                    let inventory = world.actors.get(&world.player_id()).unwrap().inventory.clone();
+                    let content = world.container_content(&container_id);
+
+                    // both panes provide enough slots to hold all items
+                    let pattern = &Pattern::MatrixWithGaps {
+                        rows: 1, cols: (inventory.len() + content.len()).max(1) as u16,
+                        width: 48.0, height: 48.0,
+                        sep_x: 2.0, sep_y: 2.0
+                    };
+                    let mut widget = InventoryWidget::new(vec2(0.0, 0.0), &pattern, true, self.params_slots.clone())
+                        .with_keys(vec!());
+                    let mut content_widget = InventoryWidget::new(vec2(0.0, 0.0), &pattern, true, self.params_slots.clone());
+
+                    let pos = world.player_pos();
+                    let map_pos = pos - self.viewport.top_left();
+                    if let Some(screen_pos) = self.main_map.tile_to_screen(&map_pos) {
+                        // TODO: adding the base here is a mess and will eventually
+                        // lead to an error. We should consider putting the offset
+                        // somewhere, where it is automatically used.
+                        let screen_pos = screen_pos + self.main_map_pos;
+                        // the container content is right above the player,
+                        // the player's inventory is above the container content
+                        let screen_pos = screen_pos - vec2(0.0, 48.0);
+                        content_widget.set_pos(&screen_pos);
+                        let screen_pos = screen_pos - vec2(0.0, 48.0 + 2.0 * self.params_info.font_size as f32);
+                        widget.set_pos(&screen_pos);
+
+                        self.input_mode = InputMode::Container {
+                            container_id, inventory, widget, content, content_widget, hover: None
+                        };
+                    }
+                },
                 Action::MoveViewport { dx, dy } => {
                     if dy != 0 {
                         //if viewport.y1 + dy > 0 {
@@ -707,7 +1116,7 @@ impl MainState {
                     if let Some(player) = world.actors.get(&world.player_id()) {
                         for (n, item_id) in player.inventory.iter().enumerate() {
                             if let Some(item) = world.items.get(item_id) {
-                                println!("{} - {}", n, item.description());
+                                println!("{} - {}", n, world.describe_item(item));
                             }
                         }
                     }
@@ -718,6 +1127,18 @@ impl MainState {
                 Action::GUI(GuiAction::HideShowStatus) => {
                     self.show_status = !self.show_status;
                 },
+                Action::GUI(GuiAction::HideShowEquipment) => {
+                    self.show_equipment = !self.show_equipment;
+                },
+                Action::GUI(GuiAction::HideShowJournal) => {
+                    self.show_journal = !self.show_journal;
+                },
+                Action::GUI(GuiAction::HideShowCrafting) => {
+                    self.show_crafting = !self.show_crafting;
+                },
+                Action::GUI(GuiAction::HideShowCharacter) => {
+                    self.show_character = !self.show_character;
+                },
                 Action::GUI(GuiAction::HideShowFOV) => {
                     if world.highlight_mode.is_none() {
                         world.highlight_mode = Some(HighlightMode::FOV);
@@ -746,6 +1167,25 @@ impl MainState {
                 world.highlight_mode = Some(HighlightMode::FOV); // TODO: ::Select
                 world.highlights = positions.clone();
             },
+            InputMode::Target { item_id, cursor } => {
+                // show the flight path of the projectile
+                let range = match world.items.get(item_id).map(|item| &item.kind) {
+                    Some(ItemKind::Wand(spell)) => spell.range(),
+                    _ => 0
+                };
+                let trajectory = spell::trajectory(world, world.player_pos(), *cursor, range);
+                world.highlight_mode = Some(HighlightMode::FOV); // TODO: ::Select
+                world.highlights = trajectory.path.into_iter().collect();
+                world.highlights.insert(*cursor);
+            },
+            InputMode::SelectClose { positions } => {
+                world.highlight_mode = Some(HighlightMode::FOV); // TODO: ::Select
+                world.highlights = positions.clone();
+            },
+            InputMode::SelectOpen { positions } => {
+                world.highlight_mode = Some(HighlightMode::FOV); // TODO: ::Select
+                world.highlights = positions.clone();
+            },
             InputMode::Default => {
                 let from = world.player_pos();
                 let points = &mut world.highlights;
@@ -790,9 +1230,11 @@ impl MainState {
         
         // select material for map depending on input mode
         match self.input_mode {
-            InputMode::Default | InputMode::SelectTalk { .. } | InputMode::SelectUse { .. }
+            InputMode::Default | InputMode::SelectTalk { .. } | InputMode::SelectUse { .. } | InputMode::Target { .. }
+            | InputMode::SelectClose { .. } | InputMode::SelectOpen { .. } | InputMode::Trade { .. } | InputMode::Dialogue { .. }
                 => gl_use_material(self.material_vignette),
             InputMode::UseItem { .. } | InputMode::PickUpItem { .. } | InputMode::DropItem { .. }
+            | InputMode::EquipItem { .. } | InputMode::Container { .. } | InputMode::DropAmount { .. }
             => gl_use_material(self.material_bw),
         };
 
@@ -825,7 +1267,7 @@ impl MainState {
                 let ids = world.item_ids_at(&player.pos);
                 let names = ids.iter()
                     .map(|id| world.items.get(id).unwrap())
-                    .map(|item| item.description())
+                    .map(|item| world.describe_item(item))
                     .collect::<Vec<String>>();
                 let text = names.join(", ");
                 if text.len() > 0 {
@@ -852,6 +1294,11 @@ impl MainState {
             params.color = YELLOW;
             draw_text_ex(&text, pos.x, pos.y, params);
             pos.y += vsep * 1.5;
+
+            let text = format!("day {}, {}", schedule::day(world.time), schedule::clock(world.time));
+            params.color = WHITE;
+            draw_text_ex(&text, pos.x, pos.y, params);
+            pos.y += vsep;
             
             let text = format!("health: {} / {}", player.health.value, player.health.max);
             params.color = WHITE;
@@ -861,6 +1308,19 @@ impl MainState {
             let text = format!("money: {}", player.coins);
             draw_text_ex(&text, pos.x, pos.y, params);
             pos.y += vsep;
+
+            let text = format!("load: {} / {} ({})",
+                               world.carried_weight(&world.player_id()),
+                               player.capacity(),
+                               world.encumbrance(&world.player_id()).description());
+            draw_text_ex(&text, pos.x, pos.y, params);
+            pos.y += vsep;
+
+            let text = format!("attack: {}  defense: {}",
+                               world.attack_value(&world.player_id()),
+                               world.defense_value(&world.player_id()));
+            draw_text_ex(&text, pos.x, pos.y, params);
+            pos.y += vsep;
             
             let text = format!("skills:");
             draw_text_ex(&text, pos.x, pos.y, params);
@@ -887,7 +1347,8 @@ impl MainState {
                 MessageKind::Info => Color::from([1.0, 1.0, 1.0, alpha]),
                 MessageKind::Debug => WHITE,
                 MessageKind::Inventory => Color::from([1.0, 1.0, 0.3, alpha]),
-                MessageKind::Skill => Color::from([0.0, 0.0, 1.0, alpha])
+                MessageKind::Skill => Color::from([0.0, 0.0, 1.0, alpha]),
+                MessageKind::Quest => Color::from([0.3, 1.0, 0.3, alpha])
             };
             draw_text_ex(&msg.text, pos.x, pos.y, msg_params);
             alpha *= 0.7; // blend out color
@@ -926,7 +1387,7 @@ impl MainState {
                 let label = match hover {
                     Some(hovered_id) => {
                         if let Some(item) = world.items.get(&hovered_id) {
-                            format!("pick up {}", item.description())
+                            format!("pick up {}", world.describe_item(item))
                         } else {
                             format!("pick up ?")
                         }
@@ -941,7 +1402,7 @@ impl MainState {
                 let label = match hover {
                     Some(hovered_id) => {
                         let item = world.items.get(&hovered_id).unwrap();
-                        format!("use {}", item.description())
+                        format!("use {}", world.describe_item(item))
                     },
                     None => format!("use")
                 };
@@ -953,13 +1414,62 @@ impl MainState {
                 let label = match hover {
                     Some(hovered_id) => {
                         let item = world.items.get(&hovered_id).unwrap();
-                        format!("drop {}", item.description())
+                        format!("drop {}", world.describe_item(item))
                     },
                     None => format!("drop")
                 };
                 draw_text_ex(&label, pos.x, pos.y, self.params_info);
                 widget.render(&world, &inventory, &self.item_tileset);
             },
+            InputMode::DropAmount { item_id, amount, max } => {
+                if let Some(item) = world.items.get(&item_id) {
+                    let pos = world.player_pos() - self.viewport.top_left();
+                    if let Some(screen_pos) = self.main_map.tile_to_screen(&pos) {
+                        let screen_pos = screen_pos + self.main_map_pos - vec2(0.0, 16.0);
+                        let label = format!("drop how many of {}? {} / {} (arrow keys, enter)",
+                                            world.describe_item(item), amount, max);
+                        draw_text_ex(&label, screen_pos.x, screen_pos.y, self.params_info);
+                    }
+                }
+            },
+            InputMode::EquipItem { inventory, widget, hover } => {
+                let pos = widget.top_left() - vec2(0.0, self.params_info.font_size as f32); // TODO: height of tile - extra offset
+                let label = match hover {
+                    Some(hovered_id) => {
+                        let item = world.items.get(&hovered_id).unwrap();
+                        format!("equip {}", world.describe_item(item))
+                    },
+                    None => format!("equip")
+                };
+                draw_text_ex(&label, pos.x, pos.y, self.params_info);
+                widget.render(&world, &inventory, &self.item_tileset);
+            },
+            InputMode::Container { container_id, inventory, widget, content, content_widget, hover } => {
+                let pos = widget.top_left() - vec2(0.0, self.params_info.font_size as f32); // TODO: height of tile - extra offset
+                let label = match hover {
+                    Some(hovered_id) if inventory.contains(hovered_id) => {
+                        let item = world.items.get(&hovered_id).unwrap();
+                        format!("put away {}", world.describe_item(item))
+                    },
+                    _ => format!("your inventory")
+                };
+                draw_text_ex(&label, pos.x, pos.y, self.params_info);
+                widget.render(&world, &inventory, &self.item_tileset);
+
+                let pos = content_widget.top_left() - vec2(0.0, self.params_info.font_size as f32); // TODO: height of tile - extra offset
+                let label = match hover {
+                    Some(hovered_id) if content.contains(hovered_id) => {
+                        let item = world.items.get(&hovered_id).unwrap();
+                        format!("take {}", world.describe_item(item))
+                    },
+                    _ => match world.items.get(&container_id) {
+                        Some(container) => format!("content of {}", container.description()),
+                        None => format!("content")
+                    }
+                };
+                draw_text_ex(&label, pos.x, pos.y, self.params_info);
+                content_widget.render(&world, &content, &self.item_tileset);
+            },
             _ => {}
         }
     }
@@ -976,6 +1486,7 @@ async fn main() {
     // the World contains the actual game data
     let mut world = World::new();
     demo_game::populate_world(&mut world);
+    world.update_lights();
     world.update_fov(&world.player_id());
     
     // main loop
@@ -992,7 +1503,7 @@ async fn main() {
     
     while !state.quit {
 
-        egui::render_and_update_egui(&mut state, &world);
+        egui::render_and_update_egui(&mut state, &world, &mut actions);
 
         // Update, if necessary.
         // Only update every DELTA_UPDATE intervals.
@@ -1006,6 +1517,7 @@ async fn main() {
         }
 
         state.process_actions(&mut world, &mut actions);
+        world.dispatch_events();
         state.update_fov(&mut world);
         state.render(&world);
         world.messages.flush();
@@ -24,7 +24,8 @@ pub enum MessageKind {
     Info,
     Inventory,
     Debug,
-    Skill
+    Skill,
+    Quest
 }
 
 
@@ -0,0 +1,114 @@
+//! Path finding on the map.
+//!
+//! Paths consist of horizontal and vertical steps only, just like the
+//! movement of the actors.
+//!
+
+use crate::point::Point;
+
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::{Ordering, Reverse};
+
+/// Maximum number of positions that are examined before giving up.
+pub const MAX_NODES: usize = 4000;
+
+#[derive(Debug, Eq, PartialEq)]
+struct Node {
+    estimate: Reverse<i32>,
+    pos: Point
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.estimate.cmp(&other.estimate)
+            .then_with(|| (self.pos.x, self.pos.y).cmp(&(other.pos.x, other.pos.y)))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Find the shortest path from `from` to `to` using the A* algorithm.
+/// The returned path excludes the start and includes the goal. The
+/// goal itself is always considered passable, so that a path to an
+/// occupied position (e.g. an actor) can be found.
+pub fn find_path<F>(from: Point, to: Point, is_passable: F) -> Option<Vec<Point>>
+where F: Fn(&Point) -> bool
+{
+    if from == to {
+        return Some(vec!());
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Point, Point> = HashMap::new();
+    let mut cost: HashMap<Point, i32> = HashMap::new();
+
+    open.push(Node { estimate: Reverse(from.manhattan_distance(&to)), pos: from });
+    cost.insert(from, 0);
+
+    let mut examined = 0;
+    while let Some(Node { pos, .. }) = open.pop() {
+        if pos == to {
+            let mut path = vec![pos];
+            let mut current = pos;
+            while let Some(previous) = came_from.get(&current) {
+                if *previous == from {
+                    break;
+                }
+                path.push(*previous);
+                current = *previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        examined += 1;
+        if examined > MAX_NODES {
+            return None;
+        }
+
+        let next_cost = cost[&pos] + 1;
+        for neighbour in pos.neighbours().iter() {
+            if *neighbour != to && !is_passable(neighbour) {
+                continue;
+            }
+            if cost.get(neighbour).map_or(true, |&c| next_cost < c) {
+                cost.insert(*neighbour, next_cost);
+                came_from.insert(*neighbour, pos);
+                let estimate = next_cost + neighbour.manhattan_distance(&to);
+                open.push(Node { estimate: Reverse(estimate), pos: *neighbour });
+            }
+        }
+    }
+
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::PointSet;
+
+    #[test]
+    fn path_around_a_wall() {
+        // a wall from (2, -2) to (2, 2) between start and goal
+        let wall = (-2..=2).map(|y| Point::new(2, y)).collect::<PointSet>();
+        let path = find_path(Point::new(0, 0), Point::new(4, 0), |p| !wall.contains(p)).unwrap();
+
+        assert_eq!(path.last(), Some(&Point::new(4, 0)));
+        assert!(path.iter().all(|p| !wall.contains(p)));
+        // 3 steps up, 4 steps to the right, 3 steps down
+        assert_eq!(path.len(), 10);
+    }
+
+    #[test]
+    fn no_path_to_enclosed_goal() {
+        let goal = Point::new(5, 5);
+        let walls = goal.neighbours().iter().cloned().collect::<PointSet>();
+        assert!(find_path(Point::new(0, 0), goal, |p| !walls.contains(p)).is_none());
+    }
+}
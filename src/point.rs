@@ -42,6 +42,22 @@ impl Point {
     pub fn line_to(&self, to: &Point) -> impl Iterator<Item=Point> {
         LineDrawingIterator::new(self, to)
     }
+
+    /// Return the number of horizontal and vertical steps needed to
+    /// get from this point to the other point.
+    pub fn manhattan_distance(&self, other: &Point) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// Return the four horizontally and vertically adjacent points.
+    pub fn neighbours(&self) -> [Point; 4] {
+        [
+            self.offset(0, -1),
+            self.offset(1, 0),
+            self.offset(0, 1),
+            self.offset(-1, 0)
+        ]
+    }
 }
 
 impl From<(i32, i32)> for Point {
@@ -347,4 +363,13 @@ mod tests {
         assert_eq!(n_algorithm, n_result);
         
     }
+
+    #[test]
+    fn manhattan_distance() {
+        let a = Point::new(1, 2);
+        let b = Point::new(-2, 6);
+        assert_eq!(a.manhattan_distance(&b), 7);
+        assert_eq!(b.manhattan_distance(&a), 7);
+        assert!(a.neighbours().iter().all(|n| n.manhattan_distance(&a) == 1));
+    }
 }
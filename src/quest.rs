@@ -0,0 +1,375 @@
+//! Quests and the quest journal.
+//!
+//! A Quest consists of one or more stages, which are completed one
+//! after another. A stage is completed as soon as all of its
+//! objectives are fulfilled. When the last stage is completed, the
+//! player receives the quest's rewards.
+//!
+//! Quests are read from text files, e.g.
+//!
+//! ```text
+//! quest ore
+//! title Ore for the shopkeeper
+//! stage Find some ore.
+//! objective fetch ore
+//! stage Bring the ore to the shopkeeper.
+//! objective flag ore_delivered
+//! reward coins 20
+//! reward reputation villagers 10
+//! ```
+//!
+//! Lines starting with `#` are comments. The `objective` lines
+//! belong to the preceding stage. A file may contain more than one
+//! quest.
+//!
+
+use crate::{
+    point::Point,
+    actor::{ActorId, ActorKind},
+    item::{Item, ItemKind},
+    terrain::LockId,
+    event::GameEvent,
+    faction::Faction
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    pub stages: Vec<Stage>,
+    pub rewards: Vec<Reward>
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stage {
+    pub description: String,
+    pub objectives: Vec<Objective>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Objective {
+    /// pick up an item of the given kind
+    Fetch(ItemKind),
+    /// unlock the door or container with the given lock
+    Unlock(LockId),
+    /// talk to an actor of the given kind
+    Talk(ActorKind),
+    /// reach the given position
+    Reach(Point),
+    /// wait until the given flag is set, e.g. by a dialogue
+    Flag(String)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reward {
+    Coins(u16),
+    Item(ItemKind),
+    Reputation(Faction, i32),
+    Experience(u32)
+}
+
+/// Something that happened in the game and that might bring a
+/// quest forward.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuestEvent {
+    ItemPickedUp(ItemKind),
+    Unlocked(LockId),
+    TalkedTo(ActorKind),
+    Moved(Point),
+    FlagSet(String)
+}
+
+impl QuestEvent {
+    /// Translate a game event into a quest event. Only events caused
+    /// by the player can bring a quest forward.
+    pub fn from_game_event(event: &GameEvent, player_id: &ActorId) -> Option<QuestEvent> {
+        match event {
+            GameEvent::ItemPickedUp { actor_id, kind, .. } if actor_id == player_id =>
+                Some(QuestEvent::ItemPickedUp(kind.clone())),
+            GameEvent::DoorUnlocked { actor_id, lock, .. }
+            | GameEvent::ContainerUnlocked { actor_id, lock, .. } if actor_id == player_id =>
+                Some(QuestEvent::Unlocked(*lock)),
+            GameEvent::ActorTalked { actor_id, partner_kind, .. } if actor_id == player_id =>
+                Some(QuestEvent::TalkedTo(partner_kind.clone())),
+            GameEvent::ActorMoved { actor_id, to, .. } if actor_id == player_id =>
+                Some(QuestEvent::Moved(*to)),
+            GameEvent::FlagSet { flag } => Some(QuestEvent::FlagSet(flag.clone())),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuestStatus {
+    Active,
+    Completed
+}
+
+/// Progress of a quest that has been started.
+#[derive(Debug, Clone)]
+pub struct QuestProgress {
+    pub quest_id: String,
+    pub stage: usize,
+    /// fulfilled objectives of the current stage
+    pub fulfilled: Vec<bool>,
+    pub status: QuestStatus
+}
+
+#[derive(Debug)]
+pub enum QuestError {
+    Io(std::io::Error),
+    Parse { line: usize, reason: String },
+    NoStages { quest: String },
+    NoObjectives { quest: String, stage: usize }
+}
+
+impl From<std::io::Error> for QuestError {
+    fn from(error: std::io::Error) -> Self {
+        QuestError::Io(error)
+    }
+}
+
+impl Objective {
+    fn parse(text: &str) -> Option<Objective> {
+        let (keyword, arg) = text.split_once(' ')?;
+        let arg = arg.trim();
+        match keyword {
+            "fetch" => ItemKind::from_name(arg).map(Objective::Fetch),
+            "unlock" => arg.parse().ok().map(Objective::Unlock),
+            "talk" => ActorKind::from_name(arg).map(Objective::Talk),
+            "reach" => {
+                let (x, y) = arg.split_once(' ')?;
+                let x = x.trim().parse().ok()?;
+                let y = y.trim().parse().ok()?;
+                Some(Objective::Reach(Point::from((x, y))))
+            },
+            "flag" => Some(Objective::Flag(arg.into())),
+            _ => None
+        }
+    }
+
+    /// Return true if the event fulfills the objective.
+    pub fn is_fulfilled_by(&self, event: &QuestEvent) -> bool {
+        match (self, event) {
+            (Objective::Fetch(kind), QuestEvent::ItemPickedUp(other)) => kind == other,
+            (Objective::Unlock(lock), QuestEvent::Unlocked(other)) => lock == other,
+            (Objective::Talk(kind), QuestEvent::TalkedTo(other)) => kind == other,
+            (Objective::Reach(pos), QuestEvent::Moved(other)) => pos == other,
+            (Objective::Flag(flag), QuestEvent::FlagSet(other)) => flag == other,
+            _ => false
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Objective::Fetch(kind) => format!("find {}", Item::new(kind.clone()).description()),
+            Objective::Unlock(_) => format!("find the matching lock"),
+            Objective::Talk(kind) => format!("talk to {}", kind.description()),
+            Objective::Reach(pos) => format!("go to {}, {}", pos.x, pos.y),
+            Objective::Flag(flag) => flag.replace('_', " ")
+        }
+    }
+}
+
+impl Reward {
+    fn parse(text: &str) -> Option<Reward> {
+        let (keyword, arg) = text.split_once(' ')?;
+        let arg = arg.trim();
+        match keyword {
+            "coins" => arg.parse().ok().map(Reward::Coins),
+            "item" => ItemKind::from_name(arg).map(Reward::Item),
+            "experience" => arg.parse().ok().map(Reward::Experience),
+            "reputation" => {
+                let (faction, amount) = arg.split_once(' ')?;
+                let faction = Faction::from_name(faction)?;
+                let amount = amount.trim().parse().ok()?;
+                Some(Reward::Reputation(faction, amount))
+            },
+            _ => None
+        }
+    }
+}
+
+impl Quest {
+    /// Parse all quests from the given text. The quests are not
+    /// validated.
+    pub fn parse(text: &str) -> Result<Vec<Quest>, QuestError> {
+        let mut quests: Vec<Quest> = vec!();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |reason: &str| QuestError::Parse { line: n + 1, reason: reason.into() };
+            let (keyword, rest) = match line.split_once(' ') {
+                Some((keyword, rest)) => (keyword, rest.trim()),
+                None => (line, "")
+            };
+
+            if keyword == "quest" {
+                if rest.is_empty() {
+                    return Err(error("quest without id"));
+                }
+                quests.push(Quest { id: rest.into(), ..Default::default() });
+                continue;
+            }
+
+            let quest = quests.last_mut()
+                .ok_or_else(|| error("line outside of a quest"))?;
+
+            match keyword {
+                "title" => quest.title = rest.into(),
+                "stage" => quest.stages.push(Stage { description: rest.into(), objectives: vec!() }),
+                "objective" => {
+                    let objective = Objective::parse(rest)
+                        .ok_or_else(|| error("unknown objective"))?;
+                    quest.stages.last_mut()
+                        .ok_or_else(|| error("objective without stage"))?
+                        .objectives.push(objective);
+                },
+                "reward" => {
+                    let reward = Reward::parse(rest)
+                        .ok_or_else(|| error("unknown reward"))?;
+                    quest.rewards.push(reward);
+                },
+                _ => return Err(error("unknown keyword"))
+            }
+        }
+
+        Ok(quests)
+    }
+
+    /// Check that the quest has at least one stage and that every
+    /// stage has at least one objective.
+    pub fn validate(&self) -> Result<(), QuestError> {
+        if self.stages.is_empty() {
+            return Err(QuestError::NoStages { quest: self.id.clone() });
+        }
+        for (index, stage) in self.stages.iter().enumerate() {
+            if stage.objectives.is_empty() {
+                return Err(QuestError::NoObjectives { quest: self.id.clone(), stage: index });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QuestProgress {
+    pub fn new(quest: &Quest) -> Self {
+        Self {
+            quest_id: quest.id.clone(),
+            stage: 0,
+            fulfilled: vec![false; quest.stages.first().map_or(0, |stage| stage.objectives.len())],
+            status: QuestStatus::Active
+        }
+    }
+
+    /// Mark all objectives of the current stage that are fulfilled by
+    /// the event. Return true if the current stage has been completed.
+    pub fn handle_event(&mut self, quest: &Quest, event: &QuestEvent) -> bool {
+        if self.status != QuestStatus::Active {
+            return false;
+        }
+        let stage = match quest.stages.get(self.stage) {
+            Some(stage) => stage,
+            None => return false
+        };
+        for (objective, fulfilled) in stage.objectives.iter().zip(self.fulfilled.iter_mut()) {
+            if objective.is_fulfilled_by(event) {
+                *fulfilled = true;
+            }
+        }
+        self.fulfilled.iter().all(|fulfilled| *fulfilled)
+    }
+
+    /// Move on to the next stage or complete the quest if there is
+    /// no further stage.
+    pub fn advance(&mut self, quest: &Quest) {
+        self.stage += 1;
+        match quest.stages.get(self.stage) {
+            Some(stage) => self.fulfilled = vec![false; stage.objectives.len()],
+            None => {
+                self.fulfilled.clear();
+                self.status = QuestStatus::Completed;
+            }
+        }
+    }
+}
+
+/// Read all quests from the given file and validate them.
+pub fn read_from_file<P>(path: P) -> Result<Vec<Quest>, QuestError>
+where P: AsRef<std::path::Path>
+{
+    let text = std::fs::read_to_string(path)?;
+    let quests = Quest::parse(&text)?;
+    for quest in &quests {
+        quest.validate()?;
+    }
+    Ok(quests)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "
+quest ore
+title Ore for the shopkeeper
+stage Find some ore.
+objective fetch ore
+stage Bring it back.
+objective talk shopkeeper
+objective reach 3 4
+reward coins 20
+reward item bread
+reward reputation villagers 10
+reward experience 30
+";
+
+    #[test]
+    fn parse_sample() {
+        let quests = Quest::parse(SAMPLE).unwrap();
+        assert_eq!(quests.len(), 1);
+
+        let quest = &quests[0];
+        assert!(quest.validate().is_ok());
+        assert_eq!(quest.title, "Ore for the shopkeeper");
+        assert_eq!(quest.stages.len(), 2);
+        assert_eq!(quest.stages[1].objectives,
+                   vec![Objective::Talk(ActorKind::Shopkeeper), Objective::Reach(Point::from((3, 4)))]);
+        assert_eq!(quest.rewards, vec![Reward::Coins(20), Reward::Item(ItemKind::Bread),
+                                       Reward::Reputation(Faction::Villagers, 10), Reward::Experience(30)]);
+    }
+
+    #[test]
+    fn quest_without_objectives_is_invalid() {
+        let quests = Quest::parse("quest empty\nstage Do nothing.").unwrap();
+        assert!(matches!(quests[0].validate(), Err(QuestError::NoObjectives { stage: 0, .. })));
+    }
+
+    #[test]
+    fn stages_are_completed_in_order() {
+        let quest = Quest::parse(SAMPLE).unwrap().remove(0);
+        let mut progress = QuestProgress::new(&quest);
+
+        // objectives of later stages do not count
+        assert!(!progress.handle_event(&quest, &QuestEvent::TalkedTo(ActorKind::Shopkeeper)));
+        assert!(progress.handle_event(&quest, &QuestEvent::ItemPickedUp(ItemKind::Ore)));
+        progress.advance(&quest);
+
+        assert!(!progress.handle_event(&quest, &QuestEvent::Moved(Point::from((3, 4)))));
+        assert!(progress.handle_event(&quest, &QuestEvent::TalkedTo(ActorKind::Shopkeeper)));
+        progress.advance(&quest);
+        assert_eq!(progress.status, QuestStatus::Completed);
+    }
+
+    #[test]
+    fn bundled_quests_are_valid() {
+        if let Err(error) = read_from_file("assets/quests.txt") {
+            panic!("{:?}", error);
+        }
+    }
+}
@@ -3,10 +3,18 @@ use macroquad::prelude::*;
 use crate::{
     MainState,
     world::World,
-    actor::Attribute
+    actor::Attribute,
+    action::{Action, GuiAction},
+    equipment::EquipmentSlot,
+    quest::QuestStatus,
+    schedule,
+    faction::Faction,
+    experience::MAX_RANK,
+    weather::Season,
+    InputMode
 };
 
-pub fn render_and_update_egui(state: &mut MainState, world: &World) {
+pub fn render_and_update_egui(state: &mut MainState, world: &World, actions: &mut Vec<Action>) {
 
     state.egui_has_focus = false;
     
@@ -31,15 +39,40 @@ pub fn render_and_update_egui(state: &mut MainState, world: &World) {
                                          state.viewport.x2,
                                          state.viewport.y2
                         ));
-                        ui.label(format!("game time: {}",
-                                         world.time
+                        ui.label(format!("game time: {} (day {}, {})",
+                                         world.time,
+                                         schedule::day(world.time),
+                                         schedule::clock(world.time)
                         ));
+                        ui.label(format!("daylight: {:.0}%", 100.0 * world.light.daylight));
+                        ui.label(format!("weather: {} ({}), forecast: {}",
+                                         world.weather.current.description(),
+                                         Season::at(world.time).description(),
+                                         world.weather.forecast.description()));
                         ui.label(format!("health: {} / {}",
                                          player.health.value, player.health.max));
+                        ui.label(format!("nutrition: {} / {} ({})",
+                                         player.nutrition.value, player.nutrition.max,
+                                         player.hunger().description()));
+                        if player.breath.value < player.breath.max {
+                            ui.label(format!("breath: {} / {}",
+                                             player.breath.value, player.breath.max));
+                        }
                         ui.label(format!("coins: {}",
                                          player.coins));
+                        ui.label(format!("load: {} / {} ({})",
+                                         world.carried_weight(&world.player_id()),
+                                         player.capacity(),
+                                         world.encumbrance(&world.player_id()).description()));
+                        ui.label(format!("attack: {}, defense: {}",
+                                         world.attack_value(&world.player_id()),
+                                         world.defense_value(&world.player_id())));
                         ui.label(format!("skills: {}",
                                          player.skills.iter().map(|s| s.description()).collect::<Vec<String>>().join(",")));
+                        ui.label(format!("reputation: {}",
+                                         Faction::ALL.iter()
+                                         .map(|f| format!("{} {}", f.description(), world.factions.reputation(*f)))
+                                         .collect::<Vec<String>>().join(", ")));
                         
                     }
                 });
@@ -55,6 +88,17 @@ pub fn render_and_update_egui(state: &mut MainState, world: &World) {
                     ui.label("arrow keys - move around");
                     ui.label("i - show/hide inventory");
                     ui.label("p - pick up items");
+                    ui.label("u - use an item, aim wands with arrow keys + enter");
+                    ui.label("e - equip an item");
+                    ui.label("g - show/hide equipment");
+                    ui.label("j - show/hide quest journal");
+                    ui.label("r - show/hide crafting recipes");
+                    ui.label("x - show/hide character sheet");
+                    ui.label("o - open a container");
+                    ui.label("k - close a door");
+                    ui.label("l - search for traps and secret doors");
+                    ui.label("walk into a revealed trap to disarm it");
+                    ui.label("t - talk, or tell a companion to stay/follow");
                     ui.label("c - center viewport");
                     ui.label("shift + arrow keys - scroll map");
                     ui.label("h - show/hide help");
@@ -76,6 +120,202 @@ pub fn render_and_update_egui(state: &mut MainState, world: &World) {
                 });
         };
         
+        // trade window
+        if let InputMode::Trade { shopkeeper_id } = &state.input_mode {
+            let player_id = world.player_id();
+            if let (Some(player), Some(shopkeeper)) = (world.actors.get(&player_id), world.actors.get(shopkeeper_id)) {
+                if let Some(shop) = &shopkeeper.shop {
+                    let reputation = world.reputation_with(shopkeeper_id);
+                    egui::Window::new("trade")
+                        .default_pos([screen_width() / 2.0, screen_height() / 3.0])
+                        .resizable(false)
+                        .collapsible(false)
+                        .show(egui_ctx, |ui| {
+                            ui.label(format!("your coins: {}", player.coins));
+                            ui.separator();
+                            ui.label(format!("{} offers:", shopkeeper.description()));
+                            for item_id in shopkeeper.inventory.iter() {
+                                if let Some(item) = world.items.get(item_id) {
+                                    ui.horizontal(|ui| {
+                                        ui.label(world.describe_item(item));
+                                        if ui.button(format!("buy for {}", shop.selling_price(item, reputation))).clicked() {
+                                            actions.push(Action::Buy {
+                                                actor_id: player_id,
+                                                shopkeeper_id: *shopkeeper_id,
+                                                item_id: *item_id
+                                            });
+                                        }
+                                    });
+                                }
+                            }
+                            ui.separator();
+                            ui.label("you offer:");
+                            for item_id in player.inventory.iter() {
+                                if let Some(item) = world.items.get(item_id) {
+                                    ui.horizontal(|ui| {
+                                        ui.label(world.describe_item(item));
+                                        if let Some(price) = shop.buying_price(item, reputation) {
+                                            if ui.button(format!("sell for {}", price)).clicked() {
+                                                actions.push(Action::Sell {
+                                                    actor_id: player_id,
+                                                    shopkeeper_id: *shopkeeper_id,
+                                                    item_id: *item_id
+                                                });
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+                            ui.separator();
+                            if ui.button("done").clicked() {
+                                actions.push(Action::GUI(GuiAction::SwitchMode(InputMode::Default)));
+                            }
+                        });
+                }
+            }
+        }
+
+        // dialogue window
+        if let InputMode::Dialogue { actor_id, node } = &state.input_mode {
+            let player_id = world.player_id();
+            let actor = world.actors.get(actor_id);
+            let dialogue_node = actor
+                .and_then(|actor| actor.dialogue.as_ref())
+                .and_then(|name| world.dialogues.get(name))
+                .and_then(|dialogue| dialogue.node(node));
+            if let (Some(actor), Some(dialogue_node)) = (actor, dialogue_node) {
+                egui::Window::new(actor.description())
+                    .default_pos([screen_width() / 2.0, screen_height() / 3.0])
+                    .resizable(false)
+                    .collapsible(false)
+                    .show(egui_ctx, |ui| {
+                        ui.label(&dialogue_node.text);
+                        ui.separator();
+                        for (index, choice) in dialogue_node.available_choices(world, &player_id) {
+                            if ui.button(&choice.text).clicked() {
+                                actions.push(Action::DialogueChoice { actor_id: *actor_id, choice: index });
+                            }
+                        }
+                    });
+            }
+        }
+
+        if state.show_equipment {
+            egui::Window::new("equipment")
+                .default_pos([screen_width(), 0.0])
+                .resizable(false)
+                .collapsible(false)
+                .show(egui_ctx, |ui| {
+                    if let Some(player) = &world.actors.get(&world.player_id()) {
+                        for slot in EquipmentSlot::ALL.iter() {
+                            ui.horizontal(|ui| {
+                                match player.equipment.get(slot).and_then(|id| world.items.get(id)) {
+                                    Some(item) => {
+                                        ui.label(format!("{}: {}", slot.description(), world.describe_item(item)));
+                                        if ui.button("take off").clicked() {
+                                            actions.push(Action::Unequip {
+                                                actor_id: world.player_id(),
+                                                slot: *slot
+                                            });
+                                        }
+                                    },
+                                    None => {
+                                        ui.label(format!("{}: -", slot.description()));
+                                    }
+                                }
+                            });
+                        }
+                    }
+                });
+        }
+
+        if state.show_journal {
+            egui::Window::new("journal")
+                .default_pos([screen_width(), screen_height() / 2.0])
+                .resizable(false)
+                .collapsible(false)
+                .show(egui_ctx, |ui| {
+                    if world.journal.is_empty() {
+                        ui.label("You have no quests yet.");
+                    }
+                    for progress in world.journal.iter() {
+                        if let Some(quest) = world.quests.get(&progress.quest_id) {
+                            match progress.status {
+                                QuestStatus::Active => {
+                                    ui.label(&quest.title);
+                                    if let Some(stage) = quest.stages.get(progress.stage) {
+                                        ui.label(format!("  {}", stage.description));
+                                        for (objective, fulfilled) in stage.objectives.iter().zip(progress.fulfilled.iter()) {
+                                            let mark = if *fulfilled { "x" } else { " " };
+                                            ui.label(format!("  [{}] {}", mark, objective.description()));
+                                        }
+                                    }
+                                },
+                                QuestStatus::Completed => {
+                                    ui.label(format!("{} (completed)", quest.title));
+                                }
+                            }
+                        }
+                    }
+                });
+        }
+
+        if state.show_crafting {
+            egui::Window::new("crafting")
+                .default_pos([screen_width() / 2.0, screen_height() / 2.0])
+                .resizable(false)
+                .collapsible(false)
+                .show(egui_ctx, |ui| {
+                    let player_id = world.player_id();
+                    let mut feasible = world.recipes.iter()
+                        .filter(|recipe| world.can_craft(&player_id, recipe))
+                        .peekable();
+                    if feasible.peek().is_none() {
+                        ui.label("You cannot craft anything here.");
+                    }
+                    for recipe in feasible {
+                        if ui.button(&recipe.title).clicked() {
+                            actions.push(Action::EndTurn);
+                            actions.push(Action::Craft { actor_id: player_id, recipe_id: recipe.id.clone() });
+                        }
+                    }
+                });
+        }
+
+        if state.show_character {
+            egui::Window::new("character")
+                .default_pos([screen_width() / 2.0, 0.0])
+                .resizable(false)
+                .collapsible(false)
+                .show(egui_ctx, |ui| {
+                    if let Some(player) = world.actors.get(&world.player_id()) {
+                        ui.label(format!("level: {}", player.experience.level));
+                        ui.label(format!("experience: {} / {}",
+                                         player.experience.points, player.experience.next_level()));
+                        ui.label(format!("health: {} / {}",
+                                         player.health.value, player.health.max));
+                        ui.label(format!("strength: {}", player.strength.value));
+                        ui.label(format!("attack: {}, defense: {}",
+                                         world.attack_value(&world.player_id()),
+                                         world.defense_value(&world.player_id())));
+                        ui.separator();
+                        if player.skills.is_empty() {
+                            ui.label("You have no skills.");
+                        }
+                        for skill in &player.skills {
+                            if !skill.kind.is_practiced() {
+                                ui.label(skill.description());
+                                continue;
+                            }
+                            ui.label(format!("{}: rank {} / {} ({:.0}% failure)",
+                                             skill.description(),
+                                             player.skill_rank(&skill.kind), MAX_RANK,
+                                             100.0 * player.failure_chance(&skill.kind)));
+                        }
+                    }
+                });
+        }
+
         if state.show_inventory {
             egui::Window::new("You carry the following items:")
                 .default_pos([screen_width(), screen_height()])
@@ -93,9 +333,13 @@ pub fn render_and_update_egui(state: &mut MainState, world: &World) {
                     if let Some(player) = &world.actors.get(&world.player_id()) {
                         for (n, item_id) in player.inventory.iter().enumerate() {
                             if let Some(item) = &world.items.get(&item_id) {
-                                ui.label(format!("{n} - {text}", n=n+1, text=item.description()));
+                                ui.label(format!("{n} - {text}", n=n+1, text=world.describe_item(item)));
                             }
                         }
+                        ui.separator();
+                        ui.label(format!("load: {} / {}",
+                                         world.carried_weight(&world.player_id()),
+                                         player.capacity()));
                     }
                 });
         }
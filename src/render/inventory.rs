@@ -4,7 +4,7 @@ use macroquad::prelude::*;
 
 use crate::{
     actor::Inventory,
-    item::{ItemId, item_index},
+    item::ItemId,
     world::World,
     pattern::Pattern,
 };
@@ -32,6 +32,13 @@ impl InventoryWidget {
         }
     }
 
+    /// Replace the keys that are used to select the slots. If there
+    /// are no keys, then the slots can only be selected by mouse.
+    pub fn with_keys(mut self, keys: Vec<char>) -> Self {
+        self.keys = keys;
+        self
+    }
+
     pub fn top_left(&self) -> Vec2 {
         vec2(self.pos.x, self.pos.y)
     }
@@ -50,10 +57,29 @@ impl InventoryWidget {
 
                 // draw actual item
                 if let Some(item) = world.items.get(item_id) {
-                    let index = item_index(&item);
+                    let index = world.known_items.tile_index(&item);
                     tileset.render(
                         index, vec2(rect.x, rect.y), vec2(rect.w, rect.h), WHITE
                     );
+
+                    // draw the remaining charges or durability as a
+                    // bar at the bottom
+                    if let Some(condition) = item.condition() {
+                        let color = match condition {
+                            c if c > 0.5 => GREEN,
+                            c if c > 0.25 => ORANGE,
+                            _ => RED
+                        };
+                        draw_rectangle(rect.x + 2.0, rect.y + rect.h - 6.0, (rect.w - 4.0) * condition, 4.0, color);
+                    }
+
+                    // draw size of stack in the upper right corner
+                    if item.quantity > 1 {
+                        let text = format!("{}", item.quantity);
+                        let p = self.text_params;
+                        let dim = measure_text(&text, Some(p.font), p.font_size, p.font_scale);
+                        draw_text_ex(&text, rect.x+rect.w-dim.width-2.0, rect.y+dim.height+2.0, p);
+                    }
                 }
                 
                 // draw number for keycode
@@ -1,10 +1,10 @@
 
 use crate::{
-    item::item_index,
     world::{World, HighlightMode, RenderMode},
     point::{Point, Rectangle},
     actor::{actor_index, ActorId},
-    terrain::{terrain_index, feature_index}
+    terrain::{terrain_index, feature_index},
+    weather::Weather
 };
 
 use super::{Tileset};
@@ -12,6 +12,14 @@ use super::{Tileset};
 use macroquad::prelude::*;
 
 const COLOR_VISITED: Color = Color::new(0.8, 0.8, 0.8, 1.0);
+/// Visible tiles are never drawn darker than this, so that the
+/// player can make out the adjacent tiles even in the dark.
+const MIN_BRIGHTNESS: f32 = 0.3;
+const COLOR_RAIN: Color = Color::new(0.6, 0.7, 1.0, 0.6);
+const COLOR_SNOW: Color = Color::new(1.0, 1.0, 1.0, 0.9);
+const COLOR_FOG: Color = Color::new(0.75, 0.75, 0.8, 0.45);
+/// Number of rain drops or snow flakes per tile.
+const PARTICLES_PER_TILE: u32 = 2;
 
 pub struct Map {
     target: RenderTarget,
@@ -115,6 +123,8 @@ pub enum Layer {
     Terrain { terrains: Tileset, features: Tileset },
     Actor { tileset: Tileset },
     Item { tileset: Tileset },
+    /// rain drops, snow flakes and fog
+    Weather,
     Highlight,
 }
 
@@ -171,13 +181,14 @@ impl Layer {
             (RenderMode::Hidden, _) => {},
             (RenderMode::Visible, Layer::Terrain { terrains, features }) => {
                 if let Some(terrain) = world.terrain.get(&world_pos) {
+                    let tint = light_tint(world, world_pos);
                     // draw terrain base tile
                     let index = terrain_index(&terrain);
-                    terrains.render(index, *screen_pos, *tile_size, WHITE);
+                    terrains.render(index, *screen_pos, *tile_size, tint);
                     
                     // draw terrain features
                     if let Some(index) = feature_index(&terrain) {
-                        features.render(index, *screen_pos, *tile_size, WHITE);
+                        features.render(index, *screen_pos, *tile_size, tint);
                     }            
                 }
             },
@@ -197,17 +208,23 @@ impl Layer {
                 for (_, actor) in world.actors.iter()
                     .filter(|(_, actor)| actor.pos == *world_pos) {
                         let index = actor_index(&actor);
-                        tileset.render(index, *screen_pos, *tile_size, WHITE);
+                        tileset.render(index, *screen_pos, *tile_size, light_tint(world, world_pos));
                     }
             },
             (RenderMode::Visible, Layer::Item { tileset }) => {
                 for item_id in world.item_ids_at(&world_pos) {
                     if let Some(item) = world.items.get(&item_id) {
-                        let index = item_index(&item);
-                        tileset.render(index, *screen_pos, *tile_size, WHITE);
+                        let index = world.known_items.tile_index(&item);
+                        tileset.render(index, *screen_pos, *tile_size, light_tint(world, world_pos));
                     }
                 }
             },
+            (RenderMode::Visible, Layer::Weather) => {
+                let outdoors = world.terrain.get(&world_pos).map_or(false, |tile| tile.is_outdoors());
+                if outdoors {
+                    render_weather(world.weather.current, world_pos, screen_pos, tile_size);
+                }
+            },
             (_, Layer::Highlight) => {
                 if world.highlights.contains(&world_pos) {
                     draw_rectangle_lines(screen_pos.x, screen_pos.y, tile_size.x, tile_size.y, 4.0, RED);
@@ -218,7 +235,43 @@ impl Layer {
     }    
 }
 
+/// Return the colour that a visible tile is tinted with, according
+/// to the light that falls onto it.
+fn light_tint(world: &World, pos: &Point) -> Color {
+    let light = world.light.light_at(pos);
+    let level = light.level.max(MIN_BRIGHTNESS);
+    let (r, g, b) = light.colour;
+    Color::new(r * level, g * level, b * level, 1.0)
+}
 
-
-
-
+/// Draw the weather particles onto a single tile. The particles
+/// move with the real time, not with the game time, so that they
+/// are animated even while the player is waiting.
+fn render_weather(weather: Weather, world_pos: &Point, screen_pos: &Vec2, tile_size: &Vec2) {
+    let time = get_time() as f32;
+    for n in 0..PARTICLES_PER_TILE {
+        // pseudo random, but stable offset of the particle
+        let seed = (world_pos.x as u32).wrapping_mul(73856093)
+            ^ (world_pos.y as u32).wrapping_mul(19349663)
+            ^ n.wrapping_mul(83492791);
+        let fx = (seed % 101) as f32 / 101.0;
+        let fy = (seed / 101 % 103) as f32 / 103.0;
+        match weather {
+            Weather::Rain => {
+                let y = (fy + 2.0 * time).fract();
+                let x = screen_pos.x + fx * tile_size.x;
+                let y = screen_pos.y + y * tile_size.y;
+                draw_line(x, y, x - 2.0, y + 6.0, 1.0, COLOR_RAIN);
+            },
+            Weather::Snow => {
+                let y = (fy + 0.3 * time).fract();
+                let x = (fx + 0.1 * (time + 10.0 * fy).sin()).rem_euclid(1.0);
+                draw_circle(screen_pos.x + x * tile_size.x, screen_pos.y + y * tile_size.y, 1.5, COLOR_SNOW);
+            },
+            Weather::Fog if n == 0 => {
+                draw_rectangle(screen_pos.x, screen_pos.y, tile_size.x, tile_size.y, COLOR_FOG);
+            },
+            _ => {}
+        }
+    }
+}
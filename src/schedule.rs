@@ -0,0 +1,116 @@
+//! Time of day and daily schedules.
+//!
+//! The time of day is derived from `World::time`, which counts the
+//! turns since the beginning of the game. A Schedule maps hours of
+//! the day to activities at certain places, e.g. sleeping at home
+//! from 22 o'clock or working in the shop from 8 o'clock.
+//!
+
+use crate::point::Point;
+
+pub const TURNS_PER_HOUR: i32 = 10;
+pub const HOURS_PER_DAY: i32 = 24;
+
+/// Return the hour of the day (0-23) at the given time.
+pub fn hour_of_day(time: i32) -> i32 {
+    (time / TURNS_PER_HOUR).rem_euclid(HOURS_PER_DAY)
+}
+
+/// Return the day (starting with 1) at the given time.
+pub fn day(time: i32) -> i32 {
+    time.div_euclid(TURNS_PER_HOUR * HOURS_PER_DAY) + 1
+}
+
+/// Return the time of day as hh:mm.
+pub fn clock(time: i32) -> String {
+    let minutes = time.rem_euclid(TURNS_PER_HOUR) * 60 / TURNS_PER_HOUR;
+    format!("{:02}:{:02}", hour_of_day(time), minutes)
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activity {
+    Sleep,
+    Work,
+    /// walk around the place
+    Stroll,
+    /// meet other actors at the place
+    Gather
+}
+
+impl Activity {
+    /// Maximum distance from the place while doing the activity.
+    pub fn radius(&self) -> i32 {
+        match self {
+            Activity::Sleep | Activity::Work => 0,
+            Activity::Stroll => 4,
+            Activity::Gather => 2
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduleEntry {
+    /// hour of the day at which the activity begins
+    pub hour: i32,
+    pub activity: Activity,
+    pub place: Point
+}
+
+/// The activities of an actor during a day. Each activity lasts
+/// until the next activity begins.
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    entries: Vec<ScheduleEntry>
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the activity beginning at the given hour.
+    pub fn at<P: Into<Point>>(mut self, hour: i32, activity: Activity, place: P) -> Self {
+        self.entries.push(ScheduleEntry { hour, activity, place: place.into() });
+        self.entries.sort_by_key(|entry| entry.hour);
+        self
+    }
+
+    /// Return the activity at the given hour. Before the first
+    /// activity of the day, the last activity of the previous day
+    /// is still going on.
+    pub fn current(&self, hour: i32) -> Option<&ScheduleEntry> {
+        self.entries.iter()
+            .filter(|entry| entry.hour <= hour)
+            .last()
+            .or_else(|| self.entries.last())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_of_day() {
+        assert_eq!(hour_of_day(0), 0);
+        assert_eq!(hour_of_day(8 * TURNS_PER_HOUR + 5), 8);
+        assert_eq!(clock(8 * TURNS_PER_HOUR + 5), "08:30");
+        assert_eq!(day(HOURS_PER_DAY * TURNS_PER_HOUR), 2);
+    }
+
+    #[test]
+    fn current_activity() {
+        let home = (0, 0);
+        let shop = (5, 5);
+        let schedule = Schedule::new()
+            .at(22, Activity::Sleep, home)
+            .at(8, Activity::Work, shop);
+
+        assert_eq!(schedule.current(12).unwrap().activity, Activity::Work);
+        assert_eq!(schedule.current(23).unwrap().activity, Activity::Sleep);
+        // still asleep in the morning
+        assert_eq!(schedule.current(3).unwrap().activity, Activity::Sleep);
+    }
+}
@@ -3,6 +3,8 @@
 //! We wouldn't need this if we had an ECS.
 //!
 
+use crate::item::ItemId;
+
 #[derive(Debug, Clone)]
 pub struct Skill {
     pub kind: SkillKind,
@@ -26,6 +28,14 @@ impl Skill {
         }
     }
 
+    /// Skill that is granted as long as the given item is equipped.
+    pub fn new_equipped(kind: SkillKind, item_id: ItemId) -> Self {
+        Self {
+            kind,
+            duration: SkillDuration::WhileEquipped { item_id }
+        }
+    }
+
     pub fn description(&self) -> String {
         let kind = match self.kind {
             SkillKind::Swim => "swimming",
@@ -36,19 +46,27 @@ impl Skill {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum SkillKind {
     Swim,
     Climb,
     Vision { radius: i32 }
 }
 
+impl SkillKind {
+    /// Return true if the skill improves with use.
+    pub fn is_practiced(&self) -> bool {
+        matches!(self, SkillKind::Swim | SkillKind::Climb)
+    }
+}
+
 pub type GameTime = usize;
 
 #[derive(Debug, Clone)]
 pub enum SkillDuration {
     Permanent,
     Temporary { duration: GameTime },
+    WhileEquipped { item_id: ItemId },
 }
 
 impl From<SkillKind> for Skill {
@@ -0,0 +1,203 @@
+//! Spells cast by wands and the projectiles that carry them.
+//!
+//! A projectile flies along `Point::line_to` from the caster to the
+//! selected target. It stops at the first actor in its way or right
+//! before terrain that it cannot pass, e.g. a wall or a closed door.
+//!
+
+use crate::{
+    world::World,
+    actor::ActorId,
+    point::Point,
+    terrain::{Terrain, TerrainKind},
+    message::MessageKind
+};
+
+/// Damage dealt by a magic bolt.
+pub const BOLT_DAMAGE: u16 = 4;
+/// Radius of the area that is revealed by a light spell.
+pub const LIGHT_RADIUS: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Spell {
+    /// hurt the first actor in the way
+    Bolt,
+    /// reveal the area around the target
+    Light,
+    /// turn the first wall in the way into floor
+    Dig,
+    /// move the caster to the target
+    Teleport
+}
+
+impl Spell {
+    pub const ALL: [Spell; 4] = [Spell::Bolt, Spell::Light, Spell::Dig, Spell::Teleport];
+
+    /// Return the spell with the given name, as used in data files.
+    pub fn from_name(name: &str) -> Option<Spell> {
+        match name {
+            "bolt" => Some(Spell::Bolt),
+            "light" => Some(Spell::Light),
+            "dig" => Some(Spell::Dig),
+            "teleport" => Some(Spell::Teleport),
+            _ => None
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Spell::Bolt => "magic bolt",
+            Spell::Light => "light",
+            Spell::Dig => "digging",
+            Spell::Teleport => "teleportation"
+        }
+    }
+
+    /// Maximum distance the projectile flies.
+    pub fn range(&self) -> usize {
+        match self {
+            Spell::Bolt => 8,
+            Spell::Light => 6,
+            Spell::Dig => 4,
+            Spell::Teleport => 6
+        }
+    }
+
+    /// Number of charges of a new wand.
+    pub fn charges(&self) -> u16 {
+        match self {
+            Spell::Bolt => 6,
+            Spell::Light => 8,
+            Spell::Dig => 4,
+            Spell::Teleport => 3
+        }
+    }
+}
+
+/// What stopped a projectile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Impact {
+    Actor(ActorId),
+    Obstacle(Point),
+    /// the projectile reached its target or its maximum range
+    Nothing
+}
+
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    /// positions passed by the projectile, excluding the start
+    pub path: Vec<Point>,
+    pub impact: Impact
+}
+
+impl Trajectory {
+    /// Return the position where the projectile ends up.
+    pub fn end(&self) -> Option<Point> {
+        self.path.last().cloned()
+    }
+}
+
+/// Trace the path of a projectile from `from` towards `to`.
+pub fn trajectory(world: &World, from: Point, to: Point, range: usize) -> Trajectory {
+    let mut path = Vec::new();
+    for pos in from.line_to(&to).skip(1).take(range) {
+        if world.terrain.get(&pos).unwrap_or_default().blocks_projectiles() {
+            return Trajectory { path, impact: Impact::Obstacle(pos) };
+        }
+        path.push(pos);
+        if let Some(actor_id) = world.actor_id_at(&pos) {
+            return Trajectory { path, impact: Impact::Actor(actor_id) };
+        }
+    }
+    Trajectory { path, impact: Impact::Nothing }
+}
+
+/// The caster casts the spell towards the target position.
+pub fn cast(world: &mut World, caster_id: &ActorId, spell: Spell, target: Point) {
+    let from = match world.actors.get(caster_id) {
+        Some(caster) => caster.pos,
+        None => return
+    };
+    let trajectory = trajectory(world, from, target, spell.range());
+
+    match (spell, trajectory.impact) {
+        (Spell::Bolt, Impact::Actor(victim_id)) => {
+            if let Some(victim) = world.actors.get(&victim_id) {
+                world.messages.push(format!("The bolt hits {} ({} damage).", victim.description(), BOLT_DAMAGE));
+            }
+            world.inflict_damage(caster_id, &victim_id, BOLT_DAMAGE);
+        },
+        (Spell::Bolt, _) => {
+            world.messages.push("The bolt fizzles out.");
+        },
+        (Spell::Light, _) => {
+            let center = trajectory.end().unwrap_or(from);
+            if let Some(caster) = world.actors.get_mut(caster_id) {
+                for x in -LIGHT_RADIUS..=LIGHT_RADIUS {
+                    for y in -LIGHT_RADIUS..=LIGHT_RADIUS {
+                        caster.visited.insert(center.offset(x, y));
+                    }
+                }
+            }
+            world.messages.push((MessageKind::Skill, "A bright light illuminates the area."));
+        },
+        (Spell::Dig, Impact::Obstacle(pos)) => {
+            let is_wall = world.terrain.get(&pos).map_or(false, |tile| tile.kind == TerrainKind::Wall);
+            if is_wall {
+                world.alter_terrain(caster_id, &pos, Terrain::from(TerrainKind::StoneFloor));
+                world.messages.push("The wall crumbles to dust.");
+            } else {
+                world.messages.push("The spell bounces off.");
+            }
+        },
+        (Spell::Dig, _) => {
+            world.messages.push("There is nothing to dig through.");
+        },
+        (Spell::Teleport, _) => {
+            let destination = trajectory.path.iter().rev()
+                .find(|pos| !World::is_blocking(pos, &world.terrain, &world.actors))
+                .cloned();
+            match destination {
+                Some(pos) => {
+                    world.move_actor(caster_id, pos);
+                    world.messages.push((MessageKind::Skill, "You are teleported."));
+                },
+                None => world.messages.push("You feel a slight tingling.")
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::{Actor, ActorKind};
+
+    #[test]
+    fn projectile_stops_at_walls_and_actors() {
+        let mut world = World::new();
+        let from = world.player_pos();
+        let wall = from + Point::from((3, 0));
+        world.terrain.insert(wall, Terrain::from(TerrainKind::Wall));
+
+        let trajectory = trajectory(&world, from, from + Point::from((6, 0)), 8);
+        assert_eq!(trajectory.path.len(), 2);
+        assert_eq!(trajectory.impact, Impact::Obstacle(wall));
+
+        let npc_id = world.actors.add(Actor::new(ActorKind::Townsfolk, from + Point::from((2, 0)), 5));
+        let trajectory = super::trajectory(&world, from, wall, 8);
+        assert_eq!(trajectory.impact, Impact::Actor(npc_id));
+    }
+
+    #[test]
+    fn dig_through_wall() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let wall = world.player_pos() + Point::from((0, 2));
+        world.terrain.insert(wall, Terrain::from(TerrainKind::Wall));
+
+        cast(&mut world, &player_id, Spell::Dig, wall + Point::from((0, 2)));
+        assert_eq!(world.terrain.get(&wall).unwrap().kind, TerrainKind::StoneFloor);
+    }
+}
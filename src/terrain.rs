@@ -7,6 +7,8 @@ use crate::{
     point::Point,
     skill::SkillKind,
     message::{MessageKind, Message},
+    light::LightSource,
+    trap::Trap
 };
 
 use std::collections::HashMap;
@@ -34,6 +36,7 @@ pub type TerrainMap = HashMap<Point, Terrain>;
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TerrainKind {
+    /// outside of the map, which cannot be entered
     Empty,
     Grass,
     ThickGrass,
@@ -45,7 +48,9 @@ pub enum TerrainKind {
     StoneFloor,
     Path,
     Door(DoorState),
-    Bridge(Orientation)
+    Bridge(Orientation),
+    /// frozen shallow water
+    Ice
 }
 
 
@@ -54,7 +59,11 @@ pub enum Orientation { Horizontal, Vertical }
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub enum DoorState { Open, Closed, Locked }
+pub enum DoorState { Open, Closed, Locked(LockId) }
+
+/// Identifies a lock, so that a key only fits the door (or the
+/// object) with the very same `LockId`.
+pub type LockId = u16;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -63,7 +72,14 @@ pub enum TerrainFeature {
     Flower(u8),
     Waterlily,
     Stones,
-    Fountain
+    Fountain,
+    /// needed to forge metal items
+    Anvil,
+    /// makes a wall climbable
+    Ivy,
+    Trap(Trap),
+    /// a wall that turns into a door once it has been discovered
+    SecretDoor
 }
 
 impl Terrain {
@@ -88,33 +104,92 @@ impl Terrain {
             (TerrainKind::Hedge, _) |
             (TerrainKind::Wall, _) |
             (TerrainKind::Water, _) |
-            (TerrainKind::Window, _) => true,
+            (TerrainKind::Window, _) |
+            (TerrainKind::Empty, _) => true,
             (TerrainKind::ShallowWater, Some(TerrainFeature::Waterlily)) => false,
             (TerrainKind::ShallowWater, _) => true,
+            (TerrainKind::Door(DoorState::Closed), _) |
+            (TerrainKind::Door(DoorState::Locked(_)), _) => true,
             (_, Some(TerrainFeature::Fountain)) => false,
             _ => false,
         }
     }
 
+    /// Return true if the Terrain cannot be seen through, e.g. a wall
+    /// or a closed door. The opaque tile itself is still visible.
+    pub fn blocks_sight(&self) -> bool {
+        match &self.kind {
+            TerrainKind::Wall |
+            TerrainKind::Door(DoorState::Closed) |
+            TerrainKind::Door(DoorState::Locked(_)) => true,
+            _ => false
+        }
+    }
+
+    /// Return the light that the Terrain sheds. Windows only shine
+    /// at night, when the houses are lit.
+    pub fn light_source(&self, is_dark: bool) -> Option<LightSource> {
+        match (&self.kind, self.feature.as_ref()) {
+            (_, Some(TerrainFeature::Fountain)) => Some(LightSource::FOUNTAIN),
+            (TerrainKind::Window, _) if is_dark => Some(LightSource::WINDOW),
+            _ => None
+        }
+    }
+
+    /// Return true if the Terrain has a feature that the player has
+    /// not discovered yet, e.g. a hidden trap.
+    pub fn has_hidden_feature(&self) -> bool {
+        match &self.feature {
+            Some(TerrainFeature::Trap(trap)) => trap.hidden,
+            Some(TerrainFeature::SecretDoor) => true,
+            _ => false
+        }
+    }
+
+    /// Return true if the Terrain is exposed to the weather. Stone
+    /// floors are only found inside of houses.
+    pub fn is_outdoors(&self) -> bool {
+        !matches!(self.kind, TerrainKind::StoneFloor | TerrainKind::Empty)
+    }
+
+    /// Return true if an actor in this Terrain is under water and
+    /// cannot breathe.
+    pub fn is_deep_water(&self) -> bool {
+        self.kind == TerrainKind::Water
+    }
+
+    /// Return true if a projectile cannot pass the Terrain.
+    pub fn blocks_projectiles(&self) -> bool {
+        self.blocks_sight()
+            || matches!(self.kind, TerrainKind::Window | TerrainKind::Hedge)
+    }
+
     // Return access requirements for this Terrain
     pub fn access(&self) ->  TerrainAccess {
         match (&self.kind, self.feature.as_ref()) {
             (TerrainKind::ShallowWater, Some(TerrainFeature::Waterlily))
                 => TerrainAccess::Allowed,
-            (TerrainKind::ShallowWater, _)
+            (TerrainKind::ShallowWater, _) |
+            (TerrainKind::Water, _)
                 => TerrainAccess::RequireSkill(SkillKind::Swim),
-            //
             (TerrainKind::Hedge, _) |
+            (TerrainKind::Wall, Some(TerrainFeature::Ivy))
+                => TerrainAccess::RequireSkill(SkillKind::Climb),
+            //
             (TerrainKind::Wall, _) |
-            (TerrainKind::Water, _) |
-            (TerrainKind::Window, _)
+            (TerrainKind::Window, _) |
+            (TerrainKind::Empty, _)
                 => TerrainAccess::Blocked,
             //
-            (TerrainKind::Door(DoorState::Locked), _)
+            (TerrainKind::Door(DoorState::Locked(_)), _)
                 => TerrainAccess::BlockedWithMessage("The door is locked!".into()),
+            (TerrainKind::Door(DoorState::Closed), _)
+                => TerrainAccess::Openable,
             //
             (_, Some(TerrainFeature::Fountain))
                 => TerrainAccess::BlockedWithMessage("The fountain is in your way.".into()),
+            (_, Some(TerrainFeature::Trap(trap))) if !trap.hidden
+                => TerrainAccess::Disarmable,
             _
                 => TerrainAccess::Allowed
         }
@@ -127,6 +202,9 @@ pub enum TerrainAccess {
     Blocked,
     BlockedWithMessage(Message),
     RequireSkill(SkillKind),
+    Openable,
+    /// a revealed trap, which is disarmed by walking into it
+    Disarmable,
 }
 
 impl From<TerrainKind> for Terrain {
@@ -220,11 +298,15 @@ pub fn terrain_index(tile: &Terrain) -> usize {
         TerrainKind::Window => 15,
         TerrainKind::Bridge(Orientation::Vertical) => 16,
         TerrainKind::Bridge(Orientation::Horizontal) => 17, // TODO
+        TerrainKind::Ice => 12, // TODO: there is no tile for ice yet
         _ => 0,
     }
 }
 
 pub fn feature_index(tile: &Terrain) -> Option<usize> {
+    if tile.has_hidden_feature() {
+        return None;
+    }
     if let Some(feature) = &tile.feature {
         let index = match feature {
             TerrainFeature::Mushroom => 20,
@@ -232,6 +314,12 @@ pub fn feature_index(tile: &Terrain) -> Option<usize> {
             TerrainFeature::Stones => 10,
             TerrainFeature::Waterlily => 30,
             TerrainFeature::Fountain => 1,
+            // TODO: there is no tile for an anvil yet
+            TerrainFeature::Anvil => 10,
+            TerrainFeature::Ivy => 70,
+            // TODO: there are no tiles for traps yet
+            TerrainFeature::Trap(_) => 10,
+            TerrainFeature::SecretDoor => return None,
         };
         Some(index)
     } else {
@@ -0,0 +1,86 @@
+//! Shops and prices.
+//!
+//! A shopkeeper is an Actor with a `Shop`. The shop's stock is the
+//! shopkeeper's own inventory, which is refilled from the shop's
+//! `assortment` every `restock_interval` turns.
+//!
+
+use crate::item::{ItemKind, Item};
+
+#[derive(Debug, Clone)]
+pub struct Shop {
+    /// item kinds and the number of items that the shop keeps in stock
+    pub assortment: Vec<(ItemKind, u16)>,
+    /// price for which the shop sells items, in percent of their value
+    pub markup: u16,
+    /// price for which the shop buys items, in percent of their value
+    pub buyback: u16,
+    pub restock_interval: i32,
+    pub last_restock: Option<i32>,
+    /// hours of the day at which the shop opens and closes
+    pub opening_hours: Option<(i32, i32)>
+}
+
+impl Shop {
+    pub fn new(assortment: Vec<(ItemKind, u16)>) -> Self {
+        Self {
+            assortment,
+            markup: 150,
+            buyback: 50,
+            restock_interval: 100,
+            last_restock: None,
+            opening_hours: None
+        }
+    }
+
+    pub fn with_opening_hours(mut self, open: i32, close: i32) -> Self {
+        self.opening_hours = Some((open, close));
+        self
+    }
+
+    /// Return true if the shop is open at the given hour of the day.
+    /// A shop without opening hours is always open.
+    pub fn is_open(&self, hour: i32) -> bool {
+        match self.opening_hours {
+            Some((open, close)) if open <= close => hour >= open && hour < close,
+            // opening hours past midnight
+            Some((open, close)) => hour >= open || hour < close,
+            None => true
+        }
+    }
+
+    /// Return true if the shop should be restocked at the given time.
+    pub fn needs_restock(&self, time: i32) -> bool {
+        match self.last_restock {
+            Some(last) => time - last >= self.restock_interval,
+            None => true
+        }
+    }
+
+    /// Price for which the shop sells a single unit of the item. A
+    /// good reputation with the shopkeeper's faction lowers the price.
+    pub fn selling_price(&self, item: &Item, reputation: i32) -> u16 {
+        let percent = (self.markup as i32 - reputation_bonus(reputation)).max(100) as u16;
+        apply_percent(item.value(), percent).max(1)
+    }
+
+    /// Price for which the shop buys a single unit of the item.
+    /// Items without any value are not bought at all. A good
+    /// reputation with the shopkeeper's faction raises the price.
+    pub fn buying_price(&self, item: &Item, reputation: i32) -> Option<u16> {
+        let percent = (self.buyback as i32 + reputation_bonus(reputation)).max(0) as u16;
+        match item.value() {
+            0 => None,
+            value => Some(apply_percent(value, percent).max(1))
+        }
+    }
+}
+
+/// Price change in percent, depending on the reputation (-100..=100).
+fn reputation_bonus(reputation: i32) -> i32 {
+    reputation / 4
+}
+
+fn apply_percent(value: u16, percent: u16) -> u16 {
+    (value as u32 * percent as u32 / 100) as u16
+}
@@ -0,0 +1,76 @@
+//! Traps and other hidden features.
+//!
+//! Traps are terrain features that are triggered when the player
+//! steps onto them. Most traps are hidden at first, just like secret
+//! doors, which look like an ordinary wall. Hidden features are
+//! revealed by searching the area or, by chance, when the player
+//! passes by. A revealed trap can be disarmed by walking into it, but
+//! a failed attempt sets the trap off.
+//!
+//! Traps go off for anyone who steps onto them, except for members of
+//! the faction that set the trap, who know where it is.
+//!
+
+use crate::faction::Faction;
+
+/// Damage taken when falling into a pit.
+pub const PIT_DAMAGE: u16 = 3;
+/// Number of turns that an actor is held by a snare.
+pub const SNARE_TURNS: u16 = 3;
+/// NPCs within this distance hear an alarm plate.
+pub const ALARM_RADIUS: i32 = 8;
+/// Hidden features within this distance can be noticed.
+pub const PERCEPTION_RADIUS: i32 = 1;
+/// Chance to notice an adjacent hidden feature while passing by.
+pub const PERCEPTION_CHANCE: f32 = 0.15;
+/// Hidden features within this distance can be found by searching.
+pub const SEARCH_RADIUS: i32 = 2;
+/// Chance to find a hidden feature by searching.
+pub const SEARCH_CHANCE: f32 = 0.5;
+/// Chance to disarm a revealed trap.
+pub const DISARM_CHANCE: f32 = 0.6;
+/// Experience points for disarming a trap.
+pub const DISARM_POINTS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapKind {
+    /// hurts the victim
+    Pit,
+    /// holds the victim in place for a few turns
+    Snare,
+    /// alerts the enemies nearby
+    AlarmPlate
+}
+
+impl TrapKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TrapKind::Pit => "a pit",
+            TrapKind::Snare => "a snare",
+            TrapKind::AlarmPlate => "an alarm plate"
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub hidden: bool,
+    /// the faction that set the trap
+    pub owner: Option<Faction>
+}
+
+impl Trap {
+    pub fn hidden(kind: TrapKind) -> Self {
+        Self {
+            kind,
+            hidden: true,
+            owner: None
+        }
+    }
+
+    pub fn with_owner(mut self, faction: Faction) -> Self {
+        self.owner = Some(faction);
+        self
+    }
+}
@@ -0,0 +1,185 @@
+//! Weather and seasons.
+//!
+//! The season is derived from `World::time`, each season lasting a
+//! few days. The weather changes every few hours; which weather
+//! comes next depends on the season, e.g. it only snows in winter.
+//!
+//! The weather is more than decoration: fog and snow reduce the
+//! vision radius, rain fills empty bottles and in winter the shallow
+//! water freezes (see `World::update_weather`).
+//!
+
+use crate::schedule::{day, TURNS_PER_HOUR};
+
+use rand::Rng;
+
+pub const DAYS_PER_SEASON: i32 = 7;
+/// Shortest and longest duration of the weather, in hours.
+const MIN_DURATION: i32 = 3;
+const MAX_DURATION: i32 = 8;
+/// Rain fills an empty bottle every that many turns.
+pub const RAIN_FILL_INTERVAL: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter
+}
+
+impl Season {
+    /// Return the season at the given time. The game starts in spring.
+    pub fn at(time: i32) -> Season {
+        match ((day(time) - 1) / DAYS_PER_SEASON).rem_euclid(4) {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter
+        }
+    }
+
+    /// Chances of rain, fog and snow.
+    fn chances(&self) -> (f32, f32, f32) {
+        match self {
+            Season::Spring => (0.3, 0.15, 0.0),
+            Season::Summer => (0.15, 0.05, 0.0),
+            Season::Autumn => (0.35, 0.3, 0.0),
+            Season::Winter => (0.0, 0.2, 0.4)
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Season::Spring => "spring",
+            Season::Summer => "summer",
+            Season::Autumn => "autumn",
+            Season::Winter => "winter"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Fog,
+    Snow
+}
+
+impl Weather {
+    /// Pick a random weather that fits the season.
+    pub fn random<R: Rng>(season: Season, rng: &mut R) -> Weather {
+        let (rain, fog, snow) = season.chances();
+        let roll = rng.gen::<f32>();
+        if roll < rain {
+            Weather::Rain
+        } else if roll < rain + fog {
+            Weather::Fog
+        } else if roll < rain + fog + snow {
+            Weather::Snow
+        } else {
+            Weather::Clear
+        }
+    }
+
+    /// Number of tiles by which the vision radius is reduced.
+    pub fn vision_penalty(&self) -> i32 {
+        match self {
+            Weather::Fog => 3,
+            Weather::Snow => 1,
+            _ => 0
+        }
+    }
+
+    /// Message that tells the player about the new weather.
+    pub fn announcement(&self) -> &'static str {
+        match self {
+            Weather::Clear => "The sky clears up.",
+            Weather::Rain => "It starts to rain.",
+            Weather::Fog => "A thick fog rises.",
+            Weather::Snow => "It starts to snow."
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Fog => "fog",
+            Weather::Snow => "snow"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WeatherState {
+    pub current: Weather,
+    /// the weather that follows the current one
+    pub forecast: Weather,
+    /// time at which the forecast weather begins
+    pub changes_at: i32,
+    /// true while the shallow water is frozen
+    pub frozen: bool
+}
+
+impl Default for WeatherState {
+    fn default() -> Self {
+        Self {
+            current: Weather::Clear,
+            forecast: Weather::Clear,
+            changes_at: 0,
+            frozen: false
+        }
+    }
+}
+
+impl WeatherState {
+    /// Advance the weather to the given time. Returns true if the
+    /// weather has changed.
+    pub fn update<R: Rng>(&mut self, time: i32, rng: &mut R) -> bool {
+        if time < self.changes_at {
+            return false;
+        }
+        let changed = self.current != self.forecast;
+        self.current = self.forecast;
+        self.changes_at = time + rng.gen_range(MIN_DURATION..=MAX_DURATION) * TURNS_PER_HOUR;
+        self.forecast = Weather::random(Season::at(self.changes_at), rng);
+        changed
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::HOURS_PER_DAY;
+
+    #[test]
+    fn seasons_cycle() {
+        let days = |d: i32| d * HOURS_PER_DAY * TURNS_PER_HOUR;
+        assert_eq!(Season::at(0), Season::Spring);
+        assert_eq!(Season::at(days(DAYS_PER_SEASON)), Season::Summer);
+        assert_eq!(Season::at(days(3 * DAYS_PER_SEASON + 1)), Season::Winter);
+        assert_eq!(Season::at(days(4 * DAYS_PER_SEASON)), Season::Spring);
+    }
+
+    #[test]
+    fn snow_only_in_winter() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            assert_ne!(Weather::random(Season::Summer, &mut rng), Weather::Snow);
+            assert_ne!(Weather::random(Season::Winter, &mut rng), Weather::Rain);
+        }
+    }
+
+    #[test]
+    fn weather_follows_the_forecast() {
+        let mut rng = rand::thread_rng();
+        let mut weather = WeatherState { forecast: Weather::Fog, ..Default::default() };
+        assert!(weather.update(0, &mut rng));
+        assert_eq!(weather.current, Weather::Fog);
+        assert!(weather.changes_at >= MIN_DURATION * TURNS_PER_HOUR);
+        assert!(!weather.update(1, &mut rng));
+    }
+}
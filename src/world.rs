@@ -1,16 +1,41 @@
 
 use crate::{
     point::{Point, Rectangle, PointSet},
-    item::{ItemMap, ItemId, UseResult, ItemKind},
-    actor::{Actor, ActorMap, ActorId, ActorKind},
-    terrain::{Terrain, TerrainMap, TerrainKind, TerrainAccess},
+    item::{Item, ItemMap, ItemId, UseResult, ItemKind, Potion, Weight},
+    actor::{Actor, ActorMap, ActorId, ActorKind, ActorAI, Inventory, Encumbrance, Hunger, CompanionCommand},
+    terrain::{Terrain, TerrainMap, TerrainKind, TerrainAccess, TerrainFeature, DoorState},
     action::Action,
-    skill::SkillKind,
+    skill::{Skill, SkillKind, SkillDuration},
+    equipment::{EquipmentSlot, StatModifier},
     message::{Message, MessageQueue, MessageKind},
+    dialogue::Dialogue,
+    quest::{Quest, QuestProgress, QuestEvent, QuestStatus, Reward},
+    event::{EventBus, GameEvent, Acquisition},
+    schedule::hour_of_day,
+    spell,
+    faction::{Faction, Factions, Attitude, Deed},
+    identify::KnownItems,
+    craft::Recipe,
+    experience::{self, HEALTH_PER_LEVEL},
+    light::{self, LightMap},
+    weather::{WeatherState, Weather, Season, RAIN_FILL_INTERVAL},
+    trap::{self, TrapKind},
+    alteration::Alteration,
     InputMode,       
 };
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+const BASE_ATTACK: i32 = 1;
+const BASE_DEFENSE: i32 = 0;
+/// A starving actor loses one point of health every that many turns.
+const STARVATION_INTERVAL: i32 = 10;
+/// Damage taken when falling down while climbing.
+const FALL_DAMAGE: u16 = 2;
+/// Damage taken every turn when drowning.
+const DROWNING_DAMAGE: u16 = 1;
+/// The player is warned when the breath drops to this value.
+const LOW_BREATH: u16 = 3;
 
 #[derive(Debug)]
 pub struct World {
@@ -24,7 +49,22 @@ pub struct World {
     pub highlight_mode: Option<HighlightMode>,
     pub highlights: PointSet,
     pub fov: HashMap<ActorId, PointSet>,
-    pub messages: MessageQueue
+    pub messages: MessageQueue,
+    pub dialogues: HashMap<String, Dialogue>,
+    /// story flags, set e.g. by dialogue choices
+    pub flags: HashSet<String>,
+    /// quest definitions
+    pub quests: HashMap<String, Quest>,
+    /// progress of all quests that have been started
+    pub journal: Vec<QuestProgress>,
+    pub events: EventBus,
+    pub factions: Factions,
+    /// item kinds that the player has identified
+    pub known_items: KnownItems,
+    pub recipes: Vec<Recipe>,
+    /// light levels of the tiles, see `update_lights`
+    pub light: LightMap,
+    pub weather: WeatherState
 }
 
 impl World {
@@ -42,7 +82,17 @@ impl World {
             highlight_mode: None,
             highlights: PointSet::new(),
             fov: HashMap::new(),
-            messages: MessageQueue::default()
+            messages: MessageQueue::default(),
+            dialogues: HashMap::new(),
+            flags: HashSet::new(),
+            quests: HashMap::new(),
+            journal: vec!(),
+            events: EventBus::default(),
+            factions: Factions::default(),
+            known_items: KnownItems::new(&mut rand::thread_rng()),
+            recipes: vec!(),
+            light: LightMap::default(),
+            weather: WeatherState::default()
         }
     }
     
@@ -50,6 +100,19 @@ impl World {
         self.player_id
     }
 
+    /// Return the description of the item as far as the player
+    /// knows it, e.g. "a red potion" instead of "a potion of healing".
+    pub fn describe_item(&self, item: &Item) -> String {
+        self.known_items.describe(item)
+    }
+
+    /// The player learns what items of the given kind are.
+    pub fn identify(&mut self, kind: &ItemKind) {
+        if self.known_items.identify(kind) {
+            self.messages.push(format!("You identified {}.", kind.description()));
+        }
+    }
+
     pub fn player_pos(&self) -> Point {
         let id = self.player_id.clone();
         let player = self.actors.get(&id).unwrap();
@@ -85,6 +148,26 @@ impl World {
         terrain.get(pos).unwrap_or(&default_tile).is_blocking()
     }
 
+    /// Return true if the actor may enter the terrain at the given
+    /// position, e.g. water only lets swimmers pass. Other actors
+    /// are not taken into account.
+    pub fn can_enter(&self, actor_id: &ActorId, pos: &Point) -> bool {
+        let actor = match self.actors.get(actor_id) {
+            Some(actor) => actor,
+            None => return false
+        };
+        match self.terrain.get(pos).unwrap_or_default().access() {
+            TerrainAccess::RequireSkill(kind) => actor.has_skill(&kind),
+            _ => !World::tile_blocking(pos, &self.terrain)
+        }
+    }
+
+    // Defined as function, not as method, so that we don't need
+    // to borrow the whole `World` when using this function.
+    pub fn tile_blocks_sight(pos: &Point, terrain: &TerrainMap) -> bool {
+        terrain.get(pos).unwrap_or_default().blocks_sight()
+    }
+
     // Defined as function, not as method, so that we don't need
     // to borrow the whole `World` when using this function.
     pub fn actor_blocking(pos: &Point, actors: &ActorMap) -> bool {
@@ -96,22 +179,30 @@ impl World {
     pub fn use_item(&mut self, item_id: &ItemId, target: &ActorId) -> InputMode {
         if let Some(item) = self.items.get(&item_id) {
             let mut item = item.clone();
-            match item.use_item(self, &target) {
+            let kind = item.kind.clone();
+            let result = item.use_item(self, &target);
+            if let (Some(owner_id), UseResult::UsedUp | UseResult::Replace) = (item.owner, &result) {
+                self.events.emit(GameEvent::ItemUsed { actor_id: owner_id, item_id: *item_id, kind: kind.clone() });
+                // using an item reveals what it is
+                if owner_id == self.player_id {
+                    self.identify(&kind);
+                }
+            }
+            match result {
                 UseResult::UsedUp => {
-                    // remove item from owner's inventory
-                    if let Some(owner_id) = item.owner {
-                        if let Some(inventory) = self.actors.get_mut(&owner_id).map(|p| &mut p.inventory) {
-                            inventory.retain(|&x| x != *item_id)
-                        }
-                    }
-
-                    // then remove item from item list
-                    self.items.remove(&item_id);
+                    // only a single item of the stack is used up
+                    self.take_one(&item_id);
                 },
                 UseResult::Replace => {
-                    // replace item, keeping the reference
-                    // discard result
-                    let _ = self.items.replace(&item_id, item);
+                    // take a single item from the stack and put the
+                    // altered item back, so that it can be stacked
+                    // with similar items
+                    self.take_one(&item_id);
+                    item.quantity = 1;
+                    match item.owner {
+                        Some(owner_id) => { self.give_item(&owner_id, item); },
+                        None => { self.items.add(item); }
+                    }
                 },
                 UseResult::Drop => {
                     // remove item from owner's inventory
@@ -128,12 +219,67 @@ impl World {
                 UseResult::Select { positions } => {
                     return InputMode::SelectUse { positions, item_id: *item_id };
                 },
+                UseResult::Target => {
+                    let cursor = self.actors.get(target).map_or(Point::new(0, 0), |actor| actor.pos);
+                    return InputMode::Target { item_id: *item_id, cursor };
+                },
+                UseResult::Wear => self.wear_out(item_id),
                 _ => {}
             }
         }
         InputMode::Default
     }
 
+    /// Use the item on the selected position, e.g. a key on a door.
+    pub fn use_item_on(&mut self, item_id: &ItemId, target: &ActorId, pos: &Point) {
+        match Item::use_item_on(self, item_id, target, pos) {
+            UseResult::Wear => self.wear_out(item_id),
+            UseResult::Discharge => self.discharge(item_id),
+            UseResult::UsedUp => self.take_one(item_id),
+            _ => {}
+        }
+    }
+
+    /// The item loses some of its durability. An item without any
+    /// durability left breaks.
+    pub fn wear_out(&mut self, item_id: &ItemId) {
+        let broken = match self.items.get_mut(item_id).and_then(|item| item.durability.as_mut()) {
+            Some(durability) => {
+                durability.value = durability.value.saturating_sub(1);
+                durability.value == 0
+            },
+            None => return
+        };
+        if broken {
+            let description = self.items.get(item_id).map(|item| self.describe_item(item)).unwrap_or_default();
+            self.messages.push((MessageKind::Inventory, format!("{} breaks.", capitalize(&description))));
+            self.take_one(item_id);
+        }
+    }
+
+    /// The item loses one of its charges.
+    pub fn discharge(&mut self, item_id: &ItemId) {
+        let empty = match self.items.get_mut(item_id).and_then(|item| item.charges.as_mut()) {
+            Some(charges) => {
+                charges.value = charges.value.saturating_sub(1);
+                charges.value == 0
+            },
+            None => return
+        };
+        if empty {
+            let description = self.items.get(item_id).map(|item| self.describe_item(item)).unwrap_or_default();
+            self.messages.push((MessageKind::Inventory, format!("{} has no charges left.", capitalize(&description))));
+        }
+    }
+
+    /// The actor zaps the wand towards the target position, which
+    /// uses up one of the wand's charges.
+    pub fn zap(&mut self, actor_id: &ActorId, item_id: &ItemId, target: Point) {
+        if matches!(self.items.get(item_id).map(|item| &item.kind), Some(ItemKind::Wand(_))) {
+            self.use_item_on(item_id, actor_id, &target);
+        }
+    }
+
     pub fn drop_item(&mut self, item_id: &ItemId) {
         if let Some(item) = self.items.get_mut(&item_id) {
             // remove item from owner's inventory
@@ -145,11 +291,98 @@ impl World {
                 if let Some(owner) = self.actors.get(&owner_id) {
                     item.pos = Some(owner.pos);
                 }
+                self.events.emit(GameEvent::ItemDropped {
+                    actor_id: owner_id,
+                    item_id: *item_id,
+                    kind: item.kind.clone()
+                });
+            }
+        }
+    }
+
+    /// Remove a single item from the stack. If the stack is used up,
+    /// the item is removed from the owner's inventory and from the
+    /// world.
+    pub fn take_one(&mut self, item_id: &ItemId) {
+        if let Some(item) = self.items.get_mut(item_id) {
+            if item.quantity > 1 {
+                item.quantity -= 1;
+                return;
+            }
+
+            // remove item from owner's inventory
+            if let Some(owner_id) = item.owner {
+                if let Some(inventory) = self.actors.get_mut(&owner_id).map(|p| &mut p.inventory) {
+                    inventory.retain(|&x| x != *item_id)
+                }
             }
         }
+
+        // then remove item from item list
+        self.items.remove(item_id);
+    }
+
+    /// Split `amount` items off the stack. The new stack belongs to
+    /// the same owner. Returns the id of the new stack or the id of
+    /// the original stack, if `amount` covers the whole stack.
+    pub fn split_stack(&mut self, item_id: &ItemId, amount: u16) -> Option<ItemId> {
+        let item = self.items.get_mut(item_id)?;
+        if amount >= item.quantity {
+            return Some(*item_id);
+        }
+
+        item.quantity -= amount;
+        let mut new_item = item.clone();
+        new_item.quantity = amount;
+        let owner = new_item.owner;
+        let new_id = self.items.add(new_item);
+
+        if let Some(actor) = owner.and_then(|owner_id| self.actors.get_mut(&owner_id)) {
+            actor.inventory.push(new_id);
+        }
+        Some(new_id)
+    }
+
+    /// Return the stack in the inventory with which the given item
+    /// could be merged.
+    fn find_stack(&self, inventory: &Inventory, item: &Item) -> Option<ItemId> {
+        inventory.iter()
+            .find(|id| self.items.get(id).map_or(false, |other| other.stacks_with(item)))
+            .cloned()
+    }
+
+    /// Put the item into the actor's inventory. If the inventory
+    /// already holds a stack of the same kind, the item is merged
+    /// into that stack. Returns the id of the stack.
+    pub fn give_item(&mut self, actor_id: &ActorId, item: Item) -> Option<ItemId> {
+        self.acquire_item(actor_id, item, Acquisition::Other)
+    }
+
+    /// Put the item into the actor's inventory and emit an event
+    /// that tells how the actor came by the item.
+    pub fn acquire_item(&mut self, actor_id: &ActorId, mut item: Item, acquisition: Acquisition) -> Option<ItemId> {
+        let actor = self.actors.get(actor_id)?;
+        let kind = item.kind.clone();
+        let quantity = item.quantity;
+        let item_id = match self.find_stack(&actor.inventory, &item) {
+            Some(stack_id) => {
+                self.items.get_mut(&stack_id).unwrap().quantity += item.quantity;
+                stack_id
+            },
+            None => {
+                item.owner = Some(*actor_id);
+                item.pos = None;
+                let item_id = self.items.add(item);
+                self.actors.get_mut(actor_id).unwrap().inventory.push(item_id);
+                item_id
+            }
+        };
+        self.events.emit(GameEvent::ItemReceived { actor_id: *actor_id, item_id, kind, quantity, acquisition });
+        Some(item_id)
     }
 
     pub fn update_fov(&mut self, actor_id: &ActorId) {
+        let mut discovered = 0;
         if let Some(actor) = self.actors.get_mut(actor_id) {
             let mut fov = PointSet::new();
 
@@ -162,18 +395,126 @@ impl World {
                       } else {
                           acc
                       }) as i32;
+            // fog and snow limit the view
+            let radius = (radius - self.weather.current.vision_penalty()).max(2);
             
+            let terrain = &self.terrain;
+            let light = &self.light;
             let rect = Rectangle::from((-1*radius, -1*radius, 2*radius, 2*radius));
             // TODO: rect.offset(p)
             for p in rect.iter() {
                 let pos = actor.pos + p;
+
+                // a tile is only visible if there is no opaque tile
+                // (e.g. a wall or a closed door) in between
+                let is_hidden = actor.pos.line_to(&pos)
+                    .filter(|&q| q != actor.pos && q != pos)
+                    .any(|q| World::tile_blocks_sight(&q, terrain));
+                if is_hidden {
+                    continue;
+                }
+
+                // in the dark, only the adjacent tiles can be made out
+                let is_adjacent = (pos.x - actor.pos.x).abs() <= 1 && (pos.y - actor.pos.y).abs() <= 1;
+                if !is_adjacent && !light.is_visible(&pos) {
+                    continue;
+                }
+
                 fov.insert(pos);
                 // add visible tiles to visited positions as well
-                actor.visited.insert(pos);
+                if actor.visited.insert(pos) {
+                    discovered += 1;
+                }
             }
 
             self.fov.insert(actor_id.clone(), fov);
         }
+
+        // exploring the world is rewarded with experience
+        if *actor_id == self.player_id {
+            let points = match self.actors.get_mut(actor_id) {
+                Some(player) => player.experience.explore(discovered),
+                None => 0
+            };
+            if points > 0 {
+                self.gain_experience(actor_id, points);
+            }
+        }
+    }
+
+    /// Recompute the light levels from the daylight and from all
+    /// light sources, i.e. the terrain and the items on the ground
+    /// or carried by an actor.
+    pub fn update_lights(&mut self) {
+        let mut light = LightMap::new(light::daylight(self.time));
+        let is_dark = light.is_dark();
+        for (pos, tile) in self.terrain.iter() {
+            if let Some(source) = tile.light_source(is_dark) {
+                light.add_source(&source, *pos, &self.terrain);
+            }
+        }
+        for item in self.items.values() {
+            let pos = match (item.pos, item.owner) {
+                (Some(pos), _) => Some(pos),
+                (None, Some(owner_id)) => self.actors.get(&owner_id).map(|owner| owner.pos),
+                _ => None
+            };
+            if let (Some(source), Some(pos)) = (item.light_source(), pos) {
+                light.add_source(&source, pos, &self.terrain);
+            }
+        }
+        self.light = light;
+    }
+
+    /// Return true if the actor carries a light source.
+    pub fn carries_light(&self, actor_id: &ActorId) -> bool {
+        self.actors.get(actor_id).map_or(false, |actor| {
+            actor.inventory.iter().chain(actor.equipment.items())
+                .filter_map(|id| self.items.get(id))
+                .any(|item| item.light_source().is_some())
+        })
+    }
+
+    /// Open the closed door at the given position.
+    pub fn open_door(&mut self, actor_id: &ActorId, pos: &Point) {
+        if let Some(terrain) = self.terrain.get_mut(pos) {
+            if terrain.kind == TerrainKind::Door(DoorState::Closed) {
+                terrain.kind = TerrainKind::Door(DoorState::Open);
+                if *actor_id == self.player_id {
+                    self.messages.push("You open the door.");
+                }
+                self.events.emit(GameEvent::DoorOpened { actor_id: *actor_id, pos: *pos });
+                // the light now shines through the doorway
+                self.update_lights();
+                self.update_fov(actor_id);
+            }
+        }
+    }
+
+    /// Close the open door at the given position. The door cannot
+    /// be closed if there is an actor or an item in the doorway.
+    pub fn close_door(&mut self, actor_id: &ActorId, pos: &Point) {
+        if let Some(terrain) = self.terrain.get(pos) {
+            if terrain.kind != TerrainKind::Door(DoorState::Open) {
+                self.messages.push("There is no open door.");
+                return;
+            }
+        }
+
+        if self.actor_id_at(pos).is_some() || !self.item_ids_at(pos).is_empty() {
+            self.messages.push("Something is in the doorway. You cannot close the door.");
+            return;
+        }
+
+        if let Some(terrain) = self.terrain.get_mut(pos) {
+            terrain.kind = TerrainKind::Door(DoorState::Closed);
+            if *actor_id == self.player_id {
+                self.messages.push("You close the door.");
+            }
+            self.events.emit(GameEvent::DoorClosed { actor_id: *actor_id, pos: *pos });
+            self.update_lights();
+            self.update_fov(actor_id);
+        }
     }
 
     pub fn move_all_npc(&mut self) {
@@ -184,149 +525,2226 @@ impl World {
 
     pub fn pick_up(&mut self, actor_id: &ActorId, item_id: &ItemId) {
         // remove object position and set owner to 0
-        if let Some(item) = self.items.get_mut(&item_id) {
-            match item.kind {
-                // add money directly to player's stats
-                ItemKind::Money(amount) => {
-                    self.messages.push(
-                        (MessageKind::Inventory,
-                         format!("You pick up {} coins and add it to your pouch.", amount))
-                    );
-                    self.actors.get_mut(&actor_id).unwrap()
-                        .coins += amount;
-                    self.items.remove(&item_id);
-                },
-                // everything else belongs into player's inventory
-                _ => {
-                    self.messages.push(
-                        (MessageKind::Inventory,
-                         format!("You pick up {}.", item.description()))
-                    );
-                    item.owner = Some(*actor_id);
-                    item.pos = None;
-                    self.actors.get_mut(&actor_id).unwrap()
-                        .inventory.push(item_id.clone());
-                }                    
+        let item = match self.items.get(&item_id) {
+            Some(item) => item,
+            None => return
+        };
+
+        if item.container.is_some() {
+            self.messages.push(
+                (MessageKind::Inventory,
+                 format!("You cannot carry {}.", self.describe_item(item)))
+            );
+            return;
+        }
+
+        if !self.can_carry(actor_id, item) {
+            self.messages.push(
+                (MessageKind::Inventory,
+                 format!("You cannot carry {}, it is too heavy.", self.describe_item(item)))
+            );
+            return;
+        }
+
+        let kind = item.kind.clone();
+        match item.kind {
+            // add money directly to player's stats
+            ItemKind::Money => {
+                let amount = item.quantity;
+                self.messages.push(
+                    (MessageKind::Inventory,
+                     format!("You pick up {} coins and add it to your pouch.", amount))
+                );
+                self.actors.get_mut(&actor_id).unwrap()
+                    .coins += amount;
+                self.items.remove(&item_id);
+            },
+            // everything else belongs into player's inventory
+            _ => {
+                self.messages.push(
+                    (MessageKind::Inventory,
+                     format!("You pick up {}.", self.describe_item(item)))
+                );
+
+                let inventory = &self.actors.get(&actor_id).unwrap().inventory;
+                match self.find_stack(inventory, item) {
+                    // merge the item with a stack of the same kind
+                    Some(stack_id) => {
+                        let quantity = item.quantity;
+                        self.items.get_mut(&stack_id).unwrap().quantity += quantity;
+                        self.items.remove(&item_id);
+                    },
+                    None => {
+                        let item = self.items.get_mut(&item_id).unwrap();
+                        item.owner = Some(*actor_id);
+                        item.pos = None;
+                        self.actors.get_mut(&actor_id).unwrap()
+                            .inventory.push(item_id.clone());
+                    }
+                }
             }
         }
+
+        self.events.emit(GameEvent::ItemPickedUp { actor_id: *actor_id, item_id: *item_id, kind });
     }
-}
 
+    /// Set a story flag. Quests might wait for the flag.
+    pub fn set_flag(&mut self, flag: &str) {
+        self.flags.insert(flag.into());
+        self.events.emit(GameEvent::FlagSet { flag: flag.into() });
+    }
 
-#[derive(Debug, Copy, Clone)]
-pub enum ViewportMode { North, South, East, West, Center }
+    /// The actor talks to another actor.
+    pub fn talk_to(&mut self, actor_id: &ActorId, partner_id: &ActorId) {
+        if let Some(partner) = self.actors.get(partner_id) {
+            self.events.emit(GameEvent::ActorTalked {
+                actor_id: *actor_id,
+                partner_id: *partner_id,
+                partner_kind: partner.kind.clone()
+            });
+        }
+    }
 
-/// adjust the given viewport
-pub fn adjust_viewport(viewport: &mut Rectangle, border_size: &Point,
-                       pos: &Point, mode: ViewportMode)
-{
-    match mode {
-        ViewportMode::North => {
-            let dy = -1 * (viewport.y1 - pos.y + border_size.y);
-            if dy < 0 {
-                viewport.y1 += dy;
-                viewport.y2 += dy;
-            }
-        },
-        ViewportMode::South => {
-            let dy = -1 * (viewport.y2 - pos.y - border_size.y);
-            if dy > 0 {
-                viewport.y1 += dy;
-                viewport.y2 += dy;
-            }
-        },
-        ViewportMode::East => {
-            let dx = -1 * (viewport.x2 - pos.x - border_size.x);
-            if dx > 0 {
-                viewport.x1 += dx;
-                viewport.x2 += dx;
+    /// Move the actor to the given position. A companion of the actor
+    /// standing at that position swaps places with the actor.
+    pub fn move_actor(&mut self, actor_id: &ActorId, pos: Point) {
+        let from = match self.actors.get_mut(actor_id) {
+            // an actor caught in a snare must first break free
+            Some(actor) if actor.held > 0 => {
+                actor.held -= 1;
+                if *actor_id == self.player_id {
+                    self.messages.push("You struggle to free yourself.");
+                }
+                return;
+            },
+            Some(actor) => actor.pos,
+            None => return
+        };
+
+        // terrain that requires a skill might not be passed at once,
+        // and a climber who loses the grip falls down
+        if let TerrainAccess::RequireSkill(kind) = self.terrain.get(&pos).unwrap_or_default().access() {
+            if !self.use_skill(actor_id, &kind) {
+                if kind == SkillKind::Climb {
+                    self.inflict_damage(actor_id, actor_id, FALL_DAMAGE);
+                }
+                return;
             }
-        },
-        ViewportMode::West => {
-            let dx = -1 * (viewport.x1 - pos.x + border_size.x );
-            if dx < 0 {
-                viewport.x1 += dx;
-                viewport.x2 += dx;
+        }
+
+        if let Some(companion_id) = self.actor_id_at(&pos) {
+            if self.is_companion_of(&companion_id, actor_id) {
+                self.actors.get_mut(&companion_id).unwrap().pos = from;
+                self.update_fov(&companion_id);
+                self.events.emit(GameEvent::ActorMoved { actor_id: companion_id, from: pos, to: from });
             }
-        },
-        ViewportMode::Center => {
-            let delta = *pos - viewport.center();
-            viewport.x1 += delta.x;
-            viewport.x2 += delta.x;
-            viewport.y1 += delta.y;
-            viewport.y2 += delta.y;
+        }
+
+        self.actors.get_mut(actor_id).unwrap().pos = pos;
+        if self.carries_light(actor_id) {
+            self.update_lights();
+        }
+        self.update_fov(actor_id);
+        self.events.emit(GameEvent::ActorMoved { actor_id: *actor_id, from, to: pos });
+
+        if self.terrain.get(&pos).unwrap_or_default().is_deep_water() {
+            self.soak(actor_id);
+        }
+
+        self.trigger_trap(actor_id, &pos);
+        // only the player notices hidden features in passing
+        if *actor_id == self.player_id {
+            self.reveal_hidden(actor_id, trap::PERCEPTION_RADIUS, trap::PERCEPTION_CHANCE);
         }
     }
-}
 
+    /// The actor steps onto the trap at the given position, if there
+    /// is any. Members of the faction that owns the trap know where
+    /// it is and avoid it. The trap is revealed if the player sees it
+    /// go off, but stays armed.
+    fn trigger_trap(&mut self, actor_id: &ActorId, pos: &Point) {
+        let (faction, name) = match self.actors.get(actor_id) {
+            Some(actor) => (actor.faction, capitalize(&actor.description())),
+            None => return
+        };
+        let is_player = *actor_id == self.player_id;
+        let is_seen = is_player || self.fov.get(&self.player_id).map_or(false, |fov| fov.contains(pos));
+        let kind = match self.terrain.get_mut(pos).and_then(|tile| tile.feature.as_mut()) {
+            Some(TerrainFeature::Trap(trap)) if trap.owner.is_none() || trap.owner != faction => {
+                if is_seen {
+                    trap.hidden = false;
+                }
+                trap.kind
+            },
+            _ => return
+        };
 
-pub fn move_by(world: &World, actor_id: &ActorId, dx: i32, dy: i32, follow: bool)
-           -> Option<Action>
-{
-    let actor = world.actors.get(actor_id).unwrap();
-    let new_pos = actor.pos + (dx, dy).into();
-    //if !World::is_blocking(&new_pos, &world.terrain, &world.actors) {
-    let allow_movement = match world.terrain.get(&new_pos)
-        .unwrap_or_default().access() {
-            TerrainAccess::Blocked => false,
-            TerrainAccess::BlockedWithMessage(msg) => {
-                return Some(Action::DisplayMessage { msg })
+        match kind {
+            TrapKind::Pit => {
+                if is_player {
+                    self.messages.push(format!("You fall into a pit ({} damage)!", trap::PIT_DAMAGE));
+                } else if is_seen {
+                    self.messages.push(format!("{} falls into a pit.", name));
+                }
+                self.inflict_damage(actor_id, actor_id, trap::PIT_DAMAGE);
             },
-            TerrainAccess::Allowed => true,
-            TerrainAccess::RequireSkill(kind) => actor.has_skill(&kind)
-    };
+            TrapKind::Snare => {
+                if is_player {
+                    self.messages.push("You are caught in a snare!");
+                } else if is_seen {
+                    self.messages.push(format!("{} is caught in a snare.", name));
+                }
+                if let Some(actor) = self.actors.get_mut(actor_id) {
+                    actor.held = trap::SNARE_TURNS;
+                }
+            },
+            TrapKind::AlarmPlate => {
+                if is_player {
+                    self.messages.push("You step onto an alarm plate. A loud alarm sounds!");
+                } else if self.player_pos().manhattan_distance(pos) <= trap::ALARM_RADIUS {
+                    self.messages.push("A loud alarm sounds!");
+                }
+                // enemies within earshot come looking for the intruder
+                let alerted = self.actors.iter()
+                    .filter(|(_, actor)| actor.is_npc() && actor.pos.manhattan_distance(pos) <= trap::ALARM_RADIUS)
+                    .map(|(id, _)| *id)
+                    .filter(|id| id != actor_id && self.is_hostile_to(id, actor_id))
+                    .collect::<Vec<ActorId>>();
+                for id in alerted {
+                    if let Some(actor) = self.actors.get_mut(&id) {
+                        actor.ai = ActorAI::Hunt { target: *actor_id, last_seen: Some(*pos) };
+                    }
+                }
+            }
+        }
+    }
 
-    if allow_movement && !World::actor_blocking(&new_pos, &world.actors) {
-        if follow {
-            let mode = match (dx, dy) {
-                (0, -1) => ViewportMode::North,
-                (0, 1) => ViewportMode::South,
-                (1, 0) => ViewportMode::East,
-                (-1, 0) => ViewportMode::West,
-                _ => ViewportMode::Center
-            };
-            return Some(
-                Action::MoveFollow {
-                    actor_id: actor_id.clone(),
-                    pos: new_pos.clone(),
-                    mode
-                });
-        } else {
-            return Some(
-                Action::Move {
-                    actor_id: actor_id.clone(),
-                    pos: new_pos.clone()
-                });
+    /// The actor searches the area around for hidden features.
+    pub fn search(&mut self, actor_id: &ActorId) {
+        if self.reveal_hidden(actor_id, trap::SEARCH_RADIUS, trap::SEARCH_CHANCE) == 0 {
+            self.messages.push("You search the area, but find nothing.");
         }
     }
-    None
-}
 
-pub fn pick_up_items(world: &World, actor_id: &ActorId, pos: Point) -> Action
-{
-    println!("actor picks something up at {:?}", pos);
-    let items = world.items.iter()
-        .filter(|(_, item)| item.pos.is_some())
-        .filter(|(_, item)| item.pos.unwrap() == pos)
-        .map(|(id, _)| id.clone())
-        .collect::<Vec<_>>();
+    /// Each hidden feature within the radius around the actor is
+    /// revealed with the given chance. Returns the number of revealed
+    /// features.
+    fn reveal_hidden(&mut self, actor_id: &ActorId, radius: i32, chance: f32) -> usize {
+        let center = match self.actors.get(actor_id) {
+            Some(actor) => actor.pos,
+            None => return 0
+        };
+        let mut revealed = 0;
+        for p in Rectangle::from((-radius, -radius, 2 * radius + 1, 2 * radius + 1)).iter() {
+            let pos = center + p;
+            let hidden = self.terrain.get(&pos).map_or(false, |tile| tile.has_hidden_feature());
+            if !hidden || rand::random::<f32>() >= chance {
+                continue;
+            }
+            revealed += 1;
+            let tile = self.terrain.get_mut(&pos).unwrap();
+            match tile.feature.as_mut() {
+                Some(TerrainFeature::Trap(trap)) => {
+                    trap.hidden = false;
+                    let description = trap.kind.description();
+                    self.messages.push(format!("You notice {}.", description));
+                },
+                _ => {
+                    tile.kind = TerrainKind::Door(DoorState::Closed);
+                    tile.feature = None;
+                    self.messages.push("You discover a secret door!");
+                }
+            }
+        }
+        if revealed > 0 {
+            self.update_fov(actor_id);
+        }
+        revealed
+    }
 
-    return Action::PickUp { actor_id: actor_id.clone(), items };
-}
+    /// The actor tries to disarm the revealed trap at the given
+    /// position, which succeeds with the given chance. A disarmed
+    /// trap is removed, a failed attempt sets the trap off.
+    pub fn disarm(&mut self, actor_id: &ActorId, pos: &Point, chance: f32) {
+        let kind = match self.terrain.get(pos).and_then(|tile| tile.feature.as_ref()) {
+            Some(TerrainFeature::Trap(trap)) => trap.kind,
+            _ => return
+        };
+        if rand::random::<f32>() >= chance {
+            self.messages.push(format!("You fail to disarm {} and set it off!", kind.description()));
+            self.trigger_trap(actor_id, pos);
+            return;
+        }
+        if let Some(tile) = self.terrain.get_mut(pos) {
+            tile.feature = None;
+        }
+        self.messages.push((MessageKind::Skill, format!("You disarm {}.", kind.description())));
+        self.gain_experience(actor_id, trap::DISARM_POINTS);
+    }
 
+    /// Replace the terrain at the position, e.g. when the actor digs
+    /// through a wall. The lights and the fields of view of everyone
+    /// who sees the position are updated, as the change may open up
+    /// the view.
+    pub fn alter_terrain(&mut self, actor_id: &ActorId, pos: &Point, terrain: Terrain) {
+        self.terrain.insert(*pos, terrain);
+        self.update_lights();
 
-#[allow(dead_code)]
-#[derive(Debug, Clone)]
-pub enum HighlightMode {
-    Inspect,
-    Line,
-    FOV
-}
+        let mut watchers = self.fov.iter()
+            .filter(|(_, fov)| fov.contains(pos))
+            .map(|(id, _)| *id)
+            .collect::<Vec<ActorId>>();
+        watchers.push(*actor_id);
+        for watcher_id in watchers {
+            self.update_fov(&watcher_id);
+        }
+        self.events.emit(GameEvent::TerrainChanged { actor_id: *actor_id, pos: *pos });
+    }
 
+    /// Return the positions around the actor where the alteration
+    /// can be applied.
+    pub fn alterable_positions(&self, actor_id: &ActorId, alteration: Alteration) -> PointSet {
+        let actor = match self.actors.get(actor_id) {
+            Some(actor) => actor,
+            None => return PointSet::new()
+        };
+        let offsets = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+        offsets.iter()
+            .map(|offset| actor.pos.offset(offset.0, offset.1))
+            .filter(|pos| self.terrain.get(pos)
+                    .map_or(false, |tile| alteration.apply(tile, &actor.pos, pos).is_some()))
+            .collect()
+    }
 
-#[derive(Debug)]
-pub enum RenderMode {
-    Hidden,
-    Visible,
-    Visited
+    /// The actor alters the terrain at the position with a tool.
+    /// Returns true if the terrain has been changed.
+    pub fn alter(&mut self, actor_id: &ActorId, pos: &Point, alteration: Alteration) -> bool {
+        let from = match self.actors.get(actor_id) {
+            Some(actor) => actor.pos,
+            None => return false
+        };
+        let terrain = self.terrain.get(pos)
+            .and_then(|tile| alteration.apply(tile, &from, pos));
+        match terrain {
+            Some(terrain) => {
+                self.alter_terrain(actor_id, pos, terrain);
+                self.messages.push(alteration.message());
+                true
+            },
+            None => {
+                self.messages.push(alteration.nothing_to_do());
+                false
+            }
+        }
+    }
+
+    /// Swimming in deep water ruins the actor's food, and an
+    /// encumbered swimmer loses the heaviest item, which sinks to
+    /// the bottom.
+    fn soak(&mut self, actor_id: &ActorId) {
+        let is_player = *actor_id == self.player_id;
+        let inventory = match self.actors.get(actor_id) {
+            Some(actor) => actor.inventory.clone(),
+            None => return
+        };
+
+        for item_id in &inventory {
+            let item = match self.items.get(item_id) {
+                Some(item) if item.is_ruined_by_water() => item,
+                _ => continue
+            };
+            if is_player {
+                let description = self.describe_item(item);
+                self.messages.push((MessageKind::Inventory, format!("{} is soaked and ruined.", capitalize(&description))));
+            }
+            if let Some(inventory) = self.actors.get_mut(actor_id).map(|actor| &mut actor.inventory) {
+                inventory.retain(|id| id != item_id);
+            }
+            self.items.remove(item_id);
+        }
+
+        if self.encumbrance(actor_id) == Encumbrance::Unburdened {
+            return;
+        }
+        let heaviest = self.actors.get(actor_id)
+            .and_then(|actor| actor.inventory.iter()
+                      .filter_map(|id| self.items.get(id).map(|item| (*id, item.weight())))
+                      .max_by_key(|(_, weight)| *weight))
+            .map(|(id, _)| id);
+        if let Some(item_id) = heaviest {
+            if is_player {
+                let description = self.describe_item(self.items.get(&item_id).unwrap());
+                self.messages.push((MessageKind::Inventory, format!("You lose {}, which sinks to the bottom.", description)));
+            }
+            self.drop_item(&item_id);
+        }
+    }
+
+    /// The actor tries to use the skill, which might fail depending
+    /// on how well the skill has been practiced. Each try counts as
+    /// practice. Returns true if the skill has been used successfully.
+    pub fn use_skill(&mut self, actor_id: &ActorId, kind: &SkillKind) -> bool {
+        let chance = match self.actors.get(actor_id) {
+            Some(actor) => actor.failure_chance(kind),
+            None => return false
+        };
+        self.practice(actor_id, kind);
+        if rand::random::<f32>() >= chance {
+            return true;
+        }
+        if *actor_id == self.player_id {
+            let message = match kind {
+                SkillKind::Swim => "You struggle against the water.".into(),
+                SkillKind::Climb => format!("You lose your grip and fall down ({} damage).", FALL_DAMAGE),
+                SkillKind::Vision { .. } => "Your eyes fail you.".into()
+            };
+            self.messages.push((MessageKind::Skill, message));
+        }
+        false
+    }
+
+    /// Count one use of the skill. The player is told when the skill
+    /// reaches the next rank.
+    pub fn practice(&mut self, actor_id: &ActorId, kind: &SkillKind) {
+        let player_id = self.player_id;
+        if let Some(actor) = self.actors.get_mut(actor_id) {
+            let before = actor.skill_rank(kind);
+            *actor.practice.entry(kind.clone()).or_insert(0) += 1;
+            if *actor_id == player_id && actor.skill_rank(kind) > before {
+                let skill = Skill::new(kind.clone()).description();
+                self.messages.push((MessageKind::Skill, format!("Your {} skill improves.", skill)));
+            }
+        }
+    }
+
+    /// Add experience points. Each new level raises the maximum health.
+    pub fn gain_experience(&mut self, actor_id: &ActorId, points: u32) {
+        if let Some(actor) = self.actors.get_mut(actor_id) {
+            let levels = actor.experience.gain(points);
+            if levels > 0 {
+                actor.health.max += levels * HEALTH_PER_LEVEL;
+                actor.health.value += levels * HEALTH_PER_LEVEL;
+                let level = actor.experience.level;
+                self.messages.push((MessageKind::Skill, format!("Welcome to level {}!", level)));
+            }
+        }
+    }
+
+    /// Return true if the actor is a companion of the master.
+    pub fn is_companion_of(&self, actor_id: &ActorId, master_id: &ActorId) -> bool {
+        self.actors.get(actor_id)
+            .map_or(false, |actor| actor.master == Some(*master_id))
+    }
+
+    /// The animal becomes a companion of the master and follows it.
+    pub fn tame(&mut self, animal_id: &ActorId, master_id: &ActorId) {
+        if let Some(animal) = self.actors.get_mut(animal_id) {
+            if animal.master.is_none() {
+                self.messages.push(format!("{} decides to follow you.", capitalize(&animal.description())));
+            }
+            animal.master = Some(*master_id);
+            animal.ai = ActorAI::Follow { target: *master_id, last_seen: None };
+        }
+    }
+
+    /// Tell a companion to stay where it is or to follow its master.
+    pub fn command_companion(&mut self, companion_id: &ActorId, command: CompanionCommand) {
+        if let Some(companion) = self.actors.get_mut(companion_id) {
+            let master_id = match companion.master {
+                Some(master_id) => master_id,
+                None => return
+            };
+            let description = companion.description();
+            match command {
+                CompanionCommand::Stay => {
+                    companion.ai = ActorAI::DoNothing;
+                    self.messages.push(format!("You tell {} to stay.", description));
+                },
+                CompanionCommand::Follow => {
+                    companion.ai = ActorAI::Follow { target: master_id, last_seen: None };
+                    self.messages.push(format!("You tell {} to follow you.", description));
+                }
+            }
+        }
+    }
+
+    /// Return the attitude of the actor towards the player.
+    /// Companions of the player are always friendly.
+    pub fn attitude_to_player(&self, actor_id: &ActorId) -> Attitude {
+        if *actor_id == self.player_id || self.is_companion_of(actor_id, &self.player_id) {
+            return Attitude::Friendly;
+        }
+        match self.actors.get(actor_id).and_then(|actor| actor.faction) {
+            Some(faction) => self.factions.attitude_to_player(faction),
+            None => Attitude::Neutral
+        }
+    }
+
+    /// Return true if the actor is hostile towards the target, either
+    /// towards the player or towards the target's faction.
+    pub fn is_hostile_to(&self, actor_id: &ActorId, target: &ActorId) -> bool {
+        if *target == self.player_id {
+            return self.attitude_to_player(actor_id) == Attitude::Hostile;
+        }
+        let faction = |id| self.actors.get(id).and_then(|actor| actor.faction);
+        match (faction(actor_id), faction(target)) {
+            (Some(a), Some(b)) => self.factions.attitude(a, b) == Attitude::Hostile,
+            _ => false
+        }
+    }
+
+    /// Return the player's reputation with the actor's faction.
+    pub fn reputation_with(&self, actor_id: &ActorId) -> i32 {
+        self.actors.get(actor_id)
+            .and_then(|actor| actor.faction)
+            .map_or(0, |faction| self.factions.reputation(faction))
+    }
+
+    /// The player did something to a member of the faction, which
+    /// changes the player's reputation.
+    pub fn record_deed(&mut self, deed: Deed, victim: Faction) {
+        self.factions.record_deed(deed, victim);
+        self.messages.push(format!("Your reputation with the {} suffers.", victim.description()));
+    }
+
+    /// Advance the game time by one turn.
+    pub fn end_turn(&mut self) {
+        self.time += 1;
+        self.restock_shops();
+        self.digest();
+        self.breathe();
+        self.update_weather();
+        self.update_lights();
+        self.events.emit(GameEvent::TurnEnded { time: self.time });
+    }
+
+    /// Let the weather change and apply its effects: the shallow
+    /// water freezes in winter and the rain fills empty bottles.
+    fn update_weather(&mut self) {
+        if self.weather.update(self.time, &mut rand::thread_rng()) {
+            self.messages.push(self.weather.current.announcement());
+        }
+        self.freeze_water(Season::at(self.time) == Season::Winter);
+        if self.weather.current == Weather::Rain && self.time % RAIN_FILL_INTERVAL == 0 {
+            let player_id = self.player_id;
+            self.collect_rain(&player_id);
+        }
+    }
+
+    /// Freeze the shallow water, so that it can be walked on, or let
+    /// the ice melt.
+    fn freeze_water(&mut self, freeze: bool) {
+        if self.weather.frozen == freeze {
+            return;
+        }
+        self.weather.frozen = freeze;
+        let (from, to) = match freeze {
+            true => (TerrainKind::ShallowWater, TerrainKind::Ice),
+            false => (TerrainKind::Ice, TerrainKind::ShallowWater)
+        };
+        for tile in self.terrain.values_mut().filter(|tile| tile.kind == from) {
+            tile.kind = to.clone();
+        }
+        match freeze {
+            true => self.messages.push("The ponds freeze over."),
+            false => self.messages.push("The ice on the ponds is melting.")
+        }
+    }
+
+    /// The rain fills one of the actor's empty bottles, unless the
+    /// actor is indoors.
+    fn collect_rain(&mut self, actor_id: &ActorId) {
+        let actor = match self.actors.get(actor_id) {
+            Some(actor) => actor,
+            None => return
+        };
+        if !self.terrain.get(&actor.pos).map_or(false, |tile| tile.is_outdoors()) {
+            return;
+        }
+        let bottle_id = actor.inventory.iter()
+            .find(|id| self.items.get(id).map_or(false, |item| item.kind == ItemKind::Potion(Potion::Empty)))
+            .cloned();
+        if let Some(bottle_id) = bottle_id {
+            self.take_one(&bottle_id);
+            self.acquire_item(actor_id, Item::new(ItemKind::Potion(Potion::Water)), Acquisition::Rain);
+        }
+    }
+
+    /// Actors in deep water run out of breath, unless they are
+    /// natural swimmers, and then start to drown. Breath is restored
+    /// as soon as the actor leaves the water.
+    fn breathe(&mut self) {
+        let actor_ids = self.actors.iter().map(|(id, _)| *id).collect::<Vec<ActorId>>();
+        for actor_id in actor_ids {
+            let is_player = actor_id == self.player_id;
+            let actor = match self.actors.get_mut(&actor_id) {
+                Some(actor) => actor,
+                None => continue
+            };
+            let submerged = self.terrain.get(&actor.pos).unwrap_or_default().is_deep_water();
+            if !submerged || actor.is_natural_swimmer() {
+                actor.breath.value = actor.breath.max;
+                continue;
+            }
+
+            actor.breath.value = actor.breath.value.saturating_sub(1);
+            if actor.breath.value == 0 {
+                if is_player {
+                    self.messages.push((MessageKind::Skill, format!("You are drowning ({} damage)!", DROWNING_DAMAGE)));
+                }
+                self.inflict_damage(&actor_id, &actor_id, DROWNING_DAMAGE);
+            } else if is_player && actor.breath.value == LOW_BREATH {
+                self.messages.push((MessageKind::Skill, "You are running out of breath."));
+            }
+        }
+    }
+
+    /// The player gets hungrier with every turn. NPCs are assumed to
+    /// find their meals on their own. A starving player loses health.
+    fn digest(&mut self) {
+        let time = self.time;
+        let player = match self.actors.get_mut(&self.player_id) {
+            Some(player) => player,
+            None => return
+        };
+        let before = player.hunger();
+        player.nutrition.value = player.nutrition.value.saturating_sub(1);
+        let hunger = player.hunger();
+        if hunger != before {
+            match hunger {
+                Hunger::Hungry => self.messages.push("You are getting hungry."),
+                Hunger::Weak => self.messages.push("You feel weak with hunger."),
+                Hunger::Starving => self.messages.push("You are starving!"),
+                _ => {}
+            }
+        }
+        if hunger == Hunger::Starving && time % STARVATION_INTERVAL == 0 {
+            player.health.value = player.health.value.saturating_sub(1);
+            self.messages.push("You are starving and lose health.");
+        }
+    }
+
+    /// The actor eats the food, unless the actor is already full.
+    /// Returns true if the food has been eaten.
+    pub fn eat(&mut self, actor_id: &ActorId, food: &Item) -> bool {
+        let nutrition = match food.nutrition() {
+            Some(nutrition) => nutrition,
+            None => return false
+        };
+        let actor = match self.actors.get_mut(actor_id) {
+            Some(actor) => actor,
+            None => return false
+        };
+        if actor.hunger() == Hunger::Satiated {
+            self.messages.push("You are too full to eat anything.");
+            return false;
+        }
+        actor.nutrition.value = (actor.nutrition.value + nutrition).min(actor.nutrition.max);
+        let feeling = match actor.hunger() {
+            Hunger::Satiated => "You feel satiated.",
+            Hunger::NotHungry => "Your hunger is gone.",
+            _ => "You are still hungry."
+        };
+        let food = Item::new(food.kind.clone());
+        self.messages.push(format!("You eat {}. {}", food.description(), feeling));
+        true
+    }
+
+    /// Pass all pending events to the game systems and then to the
+    /// subscribers. Events that are emitted while dispatching are
+    /// dispatched as well.
+    pub fn dispatch_events(&mut self) {
+        while let Some(event) = self.events.next() {
+            if let Some(quest_event) = QuestEvent::from_game_event(&event, &self.player_id) {
+                self.quest_event(quest_event);
+            }
+            self.log_event(&event);
+            self.events.notify(&event);
+        }
+    }
+
+    /// Tell the player about the events that concern her, so that the
+    /// places where the world is changed need not push the messages.
+    fn log_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::ItemReceived { actor_id, kind, quantity, acquisition, .. } if *actor_id == self.player_id => {
+                let item = Item::new(kind.clone()).with_quantity(*quantity);
+                let description = self.describe_item(&item);
+                let text = match acquisition {
+                    Acquisition::Gift => format!("You receive {}.", description),
+                    Acquisition::Purchase(price) => format!("You buy {} for {} coins.", description, price),
+                    Acquisition::Craft => format!("You craft {}.", description),
+                    Acquisition::Rain => "The rain fills one of your empty bottles.".into(),
+                    Acquisition::Other => return
+                };
+                self.messages.push((MessageKind::Inventory, text));
+            },
+            _ => {}
+        }
+    }
+
+    /// Start the quest with the given id, unless it has been started before.
+    pub fn start_quest(&mut self, quest_id: &str) {
+        if self.journal.iter().any(|progress| progress.quest_id == quest_id) {
+            return;
+        }
+        let quest = match self.quests.get(quest_id) {
+            Some(quest) => quest,
+            None => {
+                self.messages.push((MessageKind::Debug, format!("Unknown quest '{}'", quest_id)));
+                return;
+            }
+        };
+        self.messages.push((MessageKind::Quest, format!("New quest: {}", quest.title)));
+        self.journal.push(QuestProgress::new(quest));
+        self.events.emit(GameEvent::QuestStarted { quest_id: quest_id.into() });
+        self.check_quest_stage(self.journal.len() - 1);
+    }
+
+    /// Return the status of the quest or None if it has not been started.
+    pub fn quest_status(&self, quest_id: &str) -> Option<QuestStatus> {
+        self.journal.iter()
+            .find(|progress| progress.quest_id == quest_id)
+            .map(|progress| progress.status)
+    }
+
+    /// Pass the event to all active quests. Only events caused by the
+    /// player should be passed.
+    fn quest_event(&mut self, event: QuestEvent) {
+        for index in 0..self.journal.len() {
+            let completed = match self.quests.get(&self.journal[index].quest_id) {
+                Some(quest) => self.journal[index].handle_event(quest, &event),
+                None => false
+            };
+            if completed {
+                self.complete_quest_stage(index);
+            }
+        }
+    }
+
+    fn complete_quest_stage(&mut self, index: usize) {
+        let quest = match self.quests.get(&self.journal[index].quest_id) {
+            Some(quest) => quest.clone(),
+            None => return
+        };
+
+        let progress = &mut self.journal[index];
+        progress.advance(&quest);
+        if progress.status == QuestStatus::Active {
+            self.messages.push(
+                (MessageKind::Quest,
+                 format!("Quest updated: {}", quest.stages[progress.stage].description))
+            );
+            self.check_quest_stage(index);
+            return;
+        }
+
+        self.messages.push((MessageKind::Quest, format!("Quest completed: {}", quest.title)));
+        self.events.emit(GameEvent::QuestCompleted { quest_id: quest.id.clone() });
+        let player_id = self.player_id;
+        for reward in &quest.rewards {
+            match reward {
+                Reward::Coins(amount) => {
+                    if let Some(player) = self.actors.get_mut(&player_id) {
+                        player.coins += amount;
+                    }
+                    self.messages.push((MessageKind::Inventory, format!("You receive {} coins.", amount)));
+                },
+                Reward::Item(kind) => {
+                    self.acquire_item(&player_id, Item::new(kind.clone()), Acquisition::Gift);
+                },
+                Reward::Reputation(faction, amount) => {
+                    self.factions.change_reputation(*faction, *amount);
+                    self.messages.push(format!("Your reputation with the {} improves.", faction.description()));
+                },
+                Reward::Experience(points) => {
+                    self.messages.push((MessageKind::Skill, format!("You gain {} experience.", points)));
+                    self.gain_experience(&player_id, *points);
+                }
+            }
+        }
+    }
+
+    /// The objectives of a new stage might already be fulfilled,
+    /// e.g. if the player already carries the item to be fetched.
+    fn check_quest_stage(&mut self, index: usize) {
+        let mut events = self.flags.iter()
+            .map(|flag| QuestEvent::FlagSet(flag.clone()))
+            .collect::<Vec<QuestEvent>>();
+        if let Some(player) = self.actors.get(&self.player_id) {
+            events.extend(player.inventory.iter()
+                          .filter_map(|id| self.items.get(id))
+                          .map(|item| QuestEvent::ItemPickedUp(item.kind.clone())));
+            events.push(QuestEvent::Moved(player.pos));
+        }
+
+        let mut completed = false;
+        if let Some(quest) = self.quests.get(&self.journal[index].quest_id) {
+            for event in &events {
+                completed = self.journal[index].handle_event(quest, event);
+            }
+        }
+        if completed {
+            self.complete_quest_stage(index);
+        }
+    }
+
+    /// Return the weight of all items that the actor carries,
+    /// including the equipped items.
+    pub fn carried_weight(&self, actor_id: &ActorId) -> Weight {
+        self.actors.get(actor_id)
+            .map(|actor| actor.inventory.iter().chain(actor.equipment.items())
+                 .filter_map(|id| self.items.get(id))
+                 .map(|item| item.weight())
+                 .sum())
+            .unwrap_or(0)
+    }
+
+    /// Return true if the actor is strong enough to carry the item
+    /// in addition to the current load.
+    pub fn can_carry(&self, actor_id: &ActorId, item: &Item) -> bool {
+        self.actors.get(actor_id)
+            .map_or(false, |actor| self.carried_weight(actor_id) + item.weight() <= actor.capacity())
+    }
+
+    pub fn encumbrance(&self, actor_id: &ActorId) -> Encumbrance {
+        self.actors.get(actor_id)
+            .map(|actor| Encumbrance::from_load(self.carried_weight(actor_id), actor.capacity()))
+            .unwrap_or(Encumbrance::Unburdened)
+    }
+
+    /// Return true if the actor has a shop which is open at the
+    /// current time of day.
+    pub fn shop_is_open(&self, shopkeeper_id: &ActorId) -> bool {
+        self.actors.get(shopkeeper_id)
+            .and_then(|actor| actor.shop.as_ref())
+            .map_or(false, |shop| shop.is_open(hour_of_day(self.time)))
+    }
+
+    /// Return true if the shopkeeper is willing to trade with the
+    /// player. Otherwise a message is shown.
+    pub fn can_trade(&mut self, shopkeeper_id: &ActorId) -> bool {
+        if !self.shop_is_open(shopkeeper_id) {
+            self.messages.push("The shop is closed.");
+            false
+        } else if self.attitude_to_player(shopkeeper_id) == Attitude::Hostile {
+            self.messages.push("The shopkeeper refuses to trade with you.");
+            false
+        } else {
+            true
+        }
+    }
+
+    /// The actor buys a single unit of the item from the
+    /// shopkeeper's stock.
+    pub fn buy(&mut self, actor_id: &ActorId, shopkeeper_id: &ActorId, item_id: &ItemId) {
+        if !self.can_trade(shopkeeper_id) {
+            return;
+        }
+
+        let reputation = self.reputation_with(shopkeeper_id);
+        let price = match self.actors.get(shopkeeper_id).and_then(|s| s.shop.as_ref())
+            .zip(self.items.get(item_id)) {
+                Some((shop, item)) => shop.selling_price(item, reputation),
+                None => return
+            };
+
+        let mut single = self.items.get(item_id).unwrap().clone();
+        single.quantity = 1;
+        let description = self.describe_item(&single);
+
+        if self.actors.get(actor_id).map_or(true, |actor| actor.coins < price) {
+            self.messages.push(format!("You cannot afford {} ({} coins).", description, price));
+            return;
+        }
+
+        if !self.can_carry(actor_id, &single) {
+            self.messages.push(format!("You cannot carry {}, it is too heavy.", description));
+            return;
+        }
+
+        self.actors.get_mut(actor_id).unwrap().coins -= price;
+        self.actors.get_mut(shopkeeper_id).unwrap().coins += price;
+        self.take_one(item_id);
+        self.acquire_item(actor_id, single, Acquisition::Purchase(price));
+    }
+
+    /// The actor sells a single unit of the item to the shopkeeper.
+    pub fn sell(&mut self, actor_id: &ActorId, shopkeeper_id: &ActorId, item_id: &ItemId) {
+        if !self.can_trade(shopkeeper_id) {
+            return;
+        }
+
+        let reputation = self.reputation_with(shopkeeper_id);
+        let price = match self.actors.get(shopkeeper_id).and_then(|s| s.shop.as_ref())
+            .zip(self.items.get(item_id)) {
+                Some((shop, item)) => shop.buying_price(item, reputation),
+                None => return
+            };
+
+        let mut single = self.items.get(item_id).unwrap().clone();
+        single.quantity = 1;
+        let description = self.describe_item(&single);
+
+        let price = match price {
+            Some(price) => price,
+            None => {
+                self.messages.push(format!("The shopkeeper is not interested in {}.", description));
+                return;
+            }
+        };
+
+        if self.actors.get(shopkeeper_id).unwrap().coins < price {
+            self.messages.push(format!("The shopkeeper cannot afford {}.", description));
+            return;
+        }
+
+        self.actors.get_mut(shopkeeper_id).unwrap().coins -= price;
+        self.actors.get_mut(actor_id).unwrap().coins += price;
+        self.take_one(item_id);
+        self.give_item(shopkeeper_id, single);
+        self.messages.push(
+            (MessageKind::Inventory,
+             format!("You sell {} for {} coins.", description, price))
+        );
+    }
+
+    /// Refill the stock of all shops whose restock interval has passed.
+    pub fn restock_shops(&mut self) {
+        let time = self.time;
+        let shops = self.actors.iter()
+            .filter_map(|(id, actor)| actor.shop.as_ref().map(|shop| (*id, shop)))
+            .filter(|(_, shop)| shop.needs_restock(time))
+            .map(|(id, shop)| (id, shop.assortment.clone()))
+            .collect::<Vec<_>>();
+
+        for (shopkeeper_id, assortment) in shops {
+            for (kind, target) in assortment {
+                let stock = self.actors.get(&shopkeeper_id).unwrap().inventory.iter()
+                    .filter_map(|id| self.items.get(id))
+                    .filter(|item| item.kind == kind)
+                    .map(|item| item.quantity)
+                    .sum::<u16>();
+
+                for _ in stock..target {
+                    self.give_item(&shopkeeper_id, Item::new(kind.clone()));
+                }
+            }
+
+            if let Some(shop) = self.actors.get_mut(&shopkeeper_id).and_then(|a| a.shop.as_mut()) {
+                shop.last_restock = Some(time);
+            }
+        }
+    }
+
+    /// Wear or wield the given item from the actor's inventory. An
+    /// item which is already in the slot is put back into the
+    /// inventory.
+    pub fn equip(&mut self, actor_id: &ActorId, item_id: &ItemId) {
+        // only items in the actor's own inventory can be equipped
+        let is_carried = self.actors.get(actor_id).map_or(false, |actor| actor.inventory.contains(item_id));
+        let item = match self.items.get(item_id) {
+            Some(item) if is_carried => item,
+            _ => return
+        };
+
+        let slot = match item.equipment_slot() {
+            Some(slot) => slot,
+            None => {
+                self.messages.push(format!("You cannot equip {}.", self.describe_item(item)));
+                return;
+            }
+        };
+        let description = self.describe_item(item);
+        let skills = item.stat_modifier().skills;
+
+        self.unequip(actor_id, &slot);
+
+        if let Some(actor) = self.actors.get_mut(actor_id) {
+            actor.inventory.retain(|&x| x != *item_id);
+            actor.equipment.equip(slot, *item_id);
+            for kind in skills {
+                actor.skills.push(Skill::new_equipped(kind, *item_id));
+            }
+            self.messages.push(
+                (MessageKind::Inventory,
+                 format!("You equip {} ({}).", description, slot.description()))
+            );
+        }
+        self.update_fov(actor_id);
+    }
+
+    /// Take off the item in the given slot and put it back into the
+    /// actor's inventory.
+    pub fn unequip(&mut self, actor_id: &ActorId, slot: &EquipmentSlot) {
+        if let Some(actor) = self.actors.get_mut(actor_id) {
+            if let Some(item_id) = actor.equipment.unequip(slot) {
+                actor.inventory.push(item_id);
+                actor.skills.retain(|skill| !matches!(skill.duration,
+                    SkillDuration::WhileEquipped { item_id: id } if id == item_id));
+                if let Some(item) = self.items.get(&item_id) {
+                    self.messages.push(
+                        (MessageKind::Inventory,
+                         format!("You take off {}.", self.describe_item(item)))
+                    );
+                }
+            }
+        }
+        self.update_fov(actor_id);
+    }
+
+    /// Return the sum of the modifiers of all items that the actor
+    /// has equipped.
+    pub fn stat_modifier(&self, actor_id: &ActorId) -> StatModifier {
+        let mut modifier = StatModifier::default();
+        if let Some(actor) = self.actors.get(actor_id) {
+            for item in actor.equipment.items().filter_map(|id| self.items.get(id)) {
+                modifier += item.stat_modifier();
+            }
+        }
+        modifier
+    }
+
+    pub fn attack_value(&self, actor_id: &ActorId) -> i32 {
+        let penalty = self.actors.get(actor_id).map_or(0, |actor| actor.hunger().attack_penalty());
+        BASE_ATTACK + self.stat_modifier(actor_id).attack - penalty
+    }
+
+    pub fn defense_value(&self, actor_id: &ActorId) -> i32 {
+        BASE_DEFENSE + self.stat_modifier(actor_id).defense
+    }
+
+    /// The attacker hits the defender in close combat. The damage is
+    /// the attack value of the attacker reduced by the defense value
+    /// of the defender, but at least one point.
+    pub fn melee_attack(&mut self, attacker_id: &ActorId, defender_id: &ActorId) {
+        let damage = (self.attack_value(attacker_id) - self.defense_value(defender_id)).max(1) as u16;
+        match (self.actors.get(attacker_id), self.actors.get(defender_id)) {
+            (Some(attacker), Some(defender)) => {
+                self.messages.push(format!("{} hits {} ({} damage).",
+                                           capitalize(&attacker.description()), defender.description(), damage));
+            },
+            _ => return
+        }
+        self.inflict_damage(attacker_id, defender_id, damage);
+    }
+
+    /// The defender loses health caused by the attacker, e.g. by a
+    /// blow or by a spell. An NPC without any health left is removed
+    /// from the world.
+    pub fn inflict_damage(&mut self, attacker_id: &ActorId, defender_id: &ActorId, damage: u16) {
+        // attacking someone is not forgotten easily
+        let victim = self.actors.get(defender_id).and_then(|defender| defender.faction);
+        let victim_kind = self.actors.get(defender_id).map(|defender| defender.kind.clone());
+        let died = self.actors.get(defender_id)
+            .map_or(false, |defender| defender.health.value <= damage && defender.is_npc());
+        if let (true, Some(faction)) = (*attacker_id == self.player_id, victim) {
+            let deed = if died { Deed::Murder } else { Deed::Assault };
+            self.record_deed(deed, faction);
+        }
+
+        if let Some(defender) = self.actors.get_mut(defender_id) {
+            defender.health.value = defender.health.value.saturating_sub(damage);
+            if defender.health.value == 0 && defender.is_npc() {
+                self.messages.push(format!("{} dies.", capitalize(&defender.description())));
+                self.events.emit(GameEvent::ActorDied { actor_id: *defender_id, kind: defender.kind.clone() });
+                self.actors.remove(defender_id);
+                self.fov.remove(defender_id);
+            }
+        }
+
+        if died {
+            if let Some(kind) = victim_kind {
+                self.gain_experience(attacker_id, experience::kill_points(&kind));
+            }
+        }
+    }
+
+    /// Try to open the container with the given id. Returns true if
+    /// the container could be opened, i.e. if it is not locked.
+    pub fn open_container(&mut self, container_id: &ItemId) -> bool {
+        if let Some(item) = self.items.get(container_id) {
+            if item.container.is_none() {
+                return false;
+            }
+            if item.is_locked() {
+                self.messages.push(format!("{} is locked.", capitalize(&item.description())));
+                return false;
+            }
+            self.messages.push(format!("You open {}.", item.description()));
+            return true;
+        }
+        false
+    }
+
+    /// Move the item from the actor's inventory into the container.
+    pub fn put_into(&mut self, actor_id: &ActorId, container_id: &ItemId, item_id: &ItemId) {
+        let (content, item) = match (self.items.get(container_id), self.items.get(item_id)) {
+            (Some(container), Some(item)) if container.container.is_some() =>
+                (container.container.as_ref().unwrap().items.clone(), item),
+            _ => return
+        };
+
+        self.messages.push(
+            (MessageKind::Inventory,
+             format!("You put away {}.", self.describe_item(item)))
+        );
+
+        if let Some(actor) = self.actors.get_mut(actor_id) {
+            actor.inventory.retain(|&x| x != *item_id);
+        }
+
+        match self.find_stack(&content, item) {
+            // merge the item with a stack of the same kind
+            Some(stack_id) => {
+                let quantity = item.quantity;
+                self.items.get_mut(&stack_id).unwrap().quantity += quantity;
+                self.items.remove(item_id);
+            },
+            None => {
+                let item = self.items.get_mut(item_id).unwrap();
+                item.owner = None;
+                item.pos = None;
+                self.items.get_mut(container_id)
+                    .and_then(|item| item.container.as_mut()).unwrap()
+                    .items.push(*item_id);
+            }
+        }
+    }
+
+    /// Move the item from the container into the actor's inventory.
+    pub fn take_out(&mut self, actor_id: &ActorId, container_id: &ItemId, item_id: &ItemId) {
+        if let Some(item) = self.items.get(item_id) {
+            if !self.can_carry(actor_id, item) {
+                self.messages.push(
+                    (MessageKind::Inventory,
+                     format!("You cannot carry {}, it is too heavy.", self.describe_item(item)))
+                );
+                return;
+            }
+        }
+
+        let owner = match self.items.get_mut(container_id)
+            .and_then(|item| item.container.as_mut()) {
+                Some(container) => {
+                    container.items.retain(|&x| x != *item_id);
+                    container.faction
+                },
+                None => return
+            };
+
+        // taking an item out of a container is just like picking it up
+        self.pick_up(actor_id, item_id);
+
+        if let Some(faction) = owner {
+            if *actor_id == self.player_id {
+                self.record_deed(Deed::Theft, faction);
+            }
+        }
+    }
+
+    /// Return the number of items of the given kind in the actor's
+    /// inventory.
+    pub fn count_items(&self, actor_id: &ActorId, kind: &ItemKind) -> u16 {
+        self.actors.get(actor_id)
+            .map(|actor| actor.inventory.iter()
+                 .filter_map(|id| self.items.get(id))
+                 .filter(|item| item.kind == *kind)
+                 .map(|item| item.quantity)
+                 .sum())
+            .unwrap_or(0)
+    }
+
+    /// Return true if the feature is at the position or next to it.
+    pub fn feature_nearby(&self, pos: &Point, feature: &TerrainFeature) -> bool {
+        (-1..=1).any(|x| (-1..=1).any(|y| {
+            self.terrain.get(&pos.offset(x, y))
+                .map_or(false, |tile| tile.feature.as_ref() == Some(feature))
+        }))
+    }
+
+    /// Return true if the actor has all inputs and tools of the
+    /// recipe and is close to the required feature.
+    pub fn can_craft(&self, actor_id: &ActorId, recipe: &Recipe) -> bool {
+        let pos = match self.actors.get(actor_id) {
+            Some(actor) => actor.pos,
+            None => return false
+        };
+        recipe.inputs.iter().all(|(kind, quantity)| self.count_items(actor_id, kind) >= *quantity)
+            && recipe.tools.iter().all(|kind| self.count_items(actor_id, kind) > 0)
+            && recipe.feature.as_ref().map_or(true, |feature| self.feature_nearby(&pos, feature))
+    }
+
+    /// Craft the recipe with the given id: the inputs are taken from
+    /// the actor's inventory and the outputs are added to it.
+    pub fn craft(&mut self, actor_id: &ActorId, recipe_id: &str) {
+        let recipe = match self.recipes.iter().find(|recipe| recipe.id == recipe_id) {
+            Some(recipe) => recipe.clone(),
+            None => return
+        };
+        if !self.can_craft(actor_id, &recipe) {
+            self.messages.push(format!("You cannot {}.", recipe.title.to_lowercase()));
+            return;
+        }
+
+        for (kind, quantity) in recipe.inputs.iter() {
+            for _ in 0..*quantity {
+                let item_id = self.actors.get(actor_id)
+                    .and_then(|actor| actor.inventory.iter()
+                              .find(|id| self.items.get(id).map_or(false, |item| item.kind == *kind))
+                              .cloned());
+                if let Some(item_id) = item_id {
+                    self.take_one(&item_id);
+                }
+            }
+        }
+
+        // the tools wear out
+        for kind in recipe.tools.iter() {
+            let tool_id = self.actors.get(actor_id)
+                .and_then(|actor| actor.inventory.iter()
+                          .find(|id| self.items.get(id).map_or(false, |item| item.kind == *kind))
+                          .cloned());
+            if let Some(tool_id) = tool_id {
+                self.wear_out(&tool_id);
+            }
+        }
+
+        for (kind, quantity) in recipe.outputs.iter() {
+            let item = Item::new(kind.clone()).with_quantity(*quantity);
+            self.acquire_item(actor_id, item, Acquisition::Craft);
+        }
+    }
+
+    /// Return the ids of the items within the given container.
+    pub fn container_content(&self, container_id: &ItemId) -> Inventory {
+        self.items.get(container_id)
+            .and_then(|item| item.container.as_ref())
+            .map(|container| container.items.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+
+#[derive(Debug, Copy, Clone)]
+pub enum ViewportMode { North, South, East, West, Center }
+
+/// adjust the given viewport
+pub fn adjust_viewport(viewport: &mut Rectangle, border_size: &Point,
+                       pos: &Point, mode: ViewportMode)
+{
+    match mode {
+        ViewportMode::North => {
+            let dy = -1 * (viewport.y1 - pos.y + border_size.y);
+            if dy < 0 {
+                viewport.y1 += dy;
+                viewport.y2 += dy;
+            }
+        },
+        ViewportMode::South => {
+            let dy = -1 * (viewport.y2 - pos.y - border_size.y);
+            if dy > 0 {
+                viewport.y1 += dy;
+                viewport.y2 += dy;
+            }
+        },
+        ViewportMode::East => {
+            let dx = -1 * (viewport.x2 - pos.x - border_size.x);
+            if dx > 0 {
+                viewport.x1 += dx;
+                viewport.x2 += dx;
+            }
+        },
+        ViewportMode::West => {
+            let dx = -1 * (viewport.x1 - pos.x + border_size.x );
+            if dx < 0 {
+                viewport.x1 += dx;
+                viewport.x2 += dx;
+            }
+        },
+        ViewportMode::Center => {
+            let delta = *pos - viewport.center();
+            viewport.x1 += delta.x;
+            viewport.x2 += delta.x;
+            viewport.y1 += delta.y;
+            viewport.y2 += delta.y;
+        }
+    }
+}
+
+
+pub fn move_by(world: &World, actor_id: &ActorId, dx: i32, dy: i32, follow: bool)
+           -> Option<Action>
+{
+    let actor = world.actors.get(actor_id).unwrap();
+    let new_pos = actor.pos + (dx, dy).into();
+    //if !World::is_blocking(&new_pos, &world.terrain, &world.actors) {
+    let allow_movement = match world.terrain.get(&new_pos)
+        .unwrap_or_default().access() {
+            TerrainAccess::Blocked => false,
+            TerrainAccess::BlockedWithMessage(msg) => {
+                return Some(Action::DisplayMessage { msg })
+            },
+            TerrainAccess::Allowed => true,
+            TerrainAccess::RequireSkill(kind) => actor.has_skill(&kind),
+            // bumping into a closed door opens it
+            TerrainAccess::Openable => {
+                return Some(Action::OpenDoor { actor_id: actor_id.clone(), pos: new_pos })
+            },
+            // bumping into a revealed trap disarms it
+            TerrainAccess::Disarmable => {
+                return Some(Action::Disarm { actor_id: actor_id.clone(), pos: new_pos })
+            }
+    };
+
+    // the player attacks enemies by bumping into them
+    if let Some(other_id) = world.actor_id_at(&new_pos) {
+        if *actor_id == world.player_id() && world.attitude_to_player(&other_id) == Attitude::Hostile {
+            return Some(Action::Attack { attacker_id: *actor_id, defender_id: other_id });
+        }
+    }
+
+    // the actor's own companions do not block the way, but swap places
+    let blocked_by_actor = match world.actor_id_at(&new_pos) {
+        Some(other_id) => !world.is_companion_of(&other_id, actor_id),
+        None => false
+    };
+
+    if allow_movement && !blocked_by_actor {
+        if follow {
+            let mode = match (dx, dy) {
+                (0, -1) => ViewportMode::North,
+                (0, 1) => ViewportMode::South,
+                (1, 0) => ViewportMode::East,
+                (-1, 0) => ViewportMode::West,
+                _ => ViewportMode::Center
+            };
+            return Some(
+                Action::MoveFollow {
+                    actor_id: actor_id.clone(),
+                    pos: new_pos.clone(),
+                    mode
+                });
+        } else {
+            return Some(
+                Action::Move {
+                    actor_id: actor_id.clone(),
+                    pos: new_pos.clone()
+                });
+        }
+    }
+    None
+}
+
+pub fn pick_up_items(world: &World, actor_id: &ActorId, pos: Point) -> Action
+{
+    println!("actor picks something up at {:?}", pos);
+    let items = world.items.iter()
+        .filter(|(_, item)| item.pos.is_some())
+        .filter(|(_, item)| item.pos.unwrap() == pos)
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<_>>();
+
+    return Action::PickUp { actor_id: actor_id.clone(), items };
+}
+
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum HighlightMode {
+    Inspect,
+    Line,
+    FOV
+}
+
+
+#[derive(Debug)]
+pub enum RenderMode {
+    Hidden,
+    Visible,
+    Visited
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade::Shop;
+    use crate::actor::{MAX_NUTRITION, MAX_BREATH};
+    use crate::trap::Trap;
+    use crate::terrain::{feature_index, Orientation};
+
+    fn world_with_door(state: DoorState) -> (World, Point) {
+        let mut world = World::new();
+        let door = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(door, Terrain::from(TerrainKind::Door(state)));
+        (world, door)
+    }
+
+    #[test]
+    fn close_and_open_door() {
+        let (mut world, door) = world_with_door(DoorState::Open);
+        let player_id = world.player_id();
+
+        world.close_door(&player_id, &door);
+        assert_eq!(world.terrain[&door].kind, TerrainKind::Door(DoorState::Closed));
+
+        world.open_door(&player_id, &door);
+        assert_eq!(world.terrain[&door].kind, TerrainKind::Door(DoorState::Open));
+    }
+
+    #[test]
+    fn close_door_refused_if_doorway_occupied() {
+        let (mut world, door) = world_with_door(DoorState::Open);
+        let player_id = world.player_id();
+        world.items.add(Item::new(ItemKind::Bread).with_pos(door));
+
+        world.close_door(&player_id, &door);
+        assert_eq!(world.terrain[&door].kind, TerrainKind::Door(DoorState::Open));
+    }
+
+    #[test]
+    fn closed_door_blocks_sight() {
+        let (mut world, door) = world_with_door(DoorState::Closed);
+        let player_id = world.player_id();
+        let behind = door + Point::from((1, 0));
+        // opening the door updates the lights, so it must be daytime
+        world.time = 12 * crate::schedule::TURNS_PER_HOUR;
+
+        world.update_fov(&player_id);
+        let fov = &world.fov[&player_id];
+        assert!(fov.contains(&door));
+        assert!(!fov.contains(&behind));
+
+        world.open_door(&player_id, &door);
+        assert!(world.fov[&player_id].contains(&behind));
+    }
+
+    #[test]
+    fn transfer_items_between_container_and_actor() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let bread = world.items.add(Item::new(ItemKind::Bread));
+        let barrel = world.items.add(
+            Item::new(ItemKind::Barrel).with_pos((1, 1)).with_content(vec![bread])
+        );
+
+        assert!(world.open_container(&barrel));
+        world.take_out(&player_id, &barrel, &bread);
+        assert!(world.container_content(&barrel).is_empty());
+        assert_eq!(world.actors.get(&player_id).unwrap().inventory, vec![bread]);
+
+        world.put_into(&player_id, &barrel, &bread);
+        assert_eq!(world.container_content(&barrel), vec![bread]);
+        assert!(world.actors.get(&player_id).unwrap().inventory.is_empty());
+    }
+
+    #[test]
+    fn locked_container_requires_matching_key() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pos = world.player_pos();
+        let chest = world.items.add(Item::new(ItemKind::Chest).with_pos(pos).with_lock(7));
+        let wrong_key = world.items.add(Item::new(ItemKind::Key(1)).with_owner(player_id));
+        let right_key = world.items.add(Item::new(ItemKind::Key(7)).with_owner(player_id));
+
+        assert!(!world.open_container(&chest));
+        world.use_item_on(&wrong_key, &player_id, &pos);
+        assert!(!world.open_container(&chest));
+        world.use_item_on(&right_key, &player_id, &pos);
+        assert!(world.open_container(&chest));
+    }
+
+    #[test]
+    fn worn_out_key_breaks() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pos = world.player_pos();
+        let key = world.give_item(&player_id, Item::new(ItemKind::Key(7)).with_durability(2)).unwrap();
+
+        world.items.add(Item::new(ItemKind::Chest).with_pos(pos).with_lock(7));
+        world.use_item_on(&key, &player_id, &pos);
+        assert!(world.describe_item(world.items.get(&key).unwrap()).contains("worn"));
+
+        world.items.add(Item::new(ItemKind::Chest).with_pos(pos).with_lock(7));
+        world.use_item_on(&key, &player_id, &pos);
+        assert!(world.items.get(&key).is_none());
+        assert!(world.actors.get(&player_id).unwrap().inventory.is_empty());
+    }
+
+    #[test]
+    fn equip_grants_modifiers_until_unequipped() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let sword = world.items.add(Item::new(ItemKind::Sword).with_owner(player_id));
+        let boots = world.items.add(Item::new(ItemKind::ClimbingBoots).with_owner(player_id));
+        world.actors.get_mut(&player_id).unwrap().inventory = vec![sword, boots];
+
+        world.equip(&player_id, &sword);
+        world.equip(&player_id, &boots);
+        let player = world.actors.get(&player_id).unwrap();
+        assert!(player.inventory.is_empty());
+        assert!(player.has_skill(&SkillKind::Climb));
+        assert_eq!(world.attack_value(&player_id), BASE_ATTACK + 3);
+
+        world.unequip(&player_id, &EquipmentSlot::Feet);
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.inventory, vec![boots]);
+        assert!(!player.has_skill(&SkillKind::Climb));
+    }
+
+    #[test]
+    fn pick_up_merges_stacks() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pos = world.player_pos();
+        let first = world.items.add(Item::new(ItemKind::Bread).with_pos(pos));
+        let second = world.items.add(Item::new(ItemKind::Bread).with_quantity(2).with_pos(pos));
+        let money = world.items.add(Item::new(ItemKind::Money).with_quantity(5).with_pos(pos));
+
+        world.pick_up(&player_id, &first);
+        world.pick_up(&player_id, &second);
+        world.pick_up(&player_id, &money);
+
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.inventory, vec![first]);
+        assert_eq!(player.coins, 5);
+        assert_eq!(world.items.get(&first).unwrap().quantity, 3);
+        assert!(world.items.get(&second).is_none());
+    }
+
+    #[test]
+    fn split_and_drop_stack() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let bread = world.give_item(&player_id, Item::new(ItemKind::Bread).with_quantity(5)).unwrap();
+
+        let dropped = world.split_stack(&bread, 2).unwrap();
+        world.drop_item(&dropped);
+
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+        assert_eq!(world.items.get(&dropped).unwrap().quantity, 2);
+        assert_eq!(world.item_ids_at(&world.player_pos()), vec![dropped]);
+        assert_eq!(world.actors.get(&player_id).unwrap().inventory, vec![bread]);
+    }
+
+    #[test]
+    fn drinking_from_stack_keeps_remaining_potions() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let potions = world.give_item(
+            &player_id, Item::new(ItemKind::Potion(Potion::Healing)).with_quantity(2)
+        ).unwrap();
+
+        world.use_item(&potions, &player_id);
+        world.use_item(&potions, &player_id);
+
+        // both potions have been replaced by a single stack of empty bottles
+        let inventory = &world.actors.get(&player_id).unwrap().inventory;
+        assert_eq!(inventory.len(), 1);
+        let bottles = world.items.get(&inventory[0]).unwrap();
+        assert_eq!(bottles.kind, ItemKind::Potion(Potion::Empty));
+        assert_eq!(bottles.quantity, 2);
+    }
+
+    #[test]
+    fn pick_up_refused_when_over_capacity() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pos = world.player_pos();
+        let capacity = world.actors.get(&player_id).unwrap().capacity();
+        let ore = world.items.add(Item::new(ItemKind::Ore).with_quantity(capacity as u16 / 5).with_pos(pos));
+        let gold = world.items.add(Item::new(ItemKind::Gold).with_pos(pos));
+
+        world.pick_up(&player_id, &ore);
+        assert_eq!(world.carried_weight(&player_id), capacity);
+        assert_eq!(world.encumbrance(&player_id), Encumbrance::Strained);
+
+        world.pick_up(&player_id, &gold);
+        assert_eq!(world.items.get(&gold).unwrap().pos, Some(pos));
+        assert_eq!(world.carried_weight(&player_id), capacity);
+    }
+
+    fn world_with_shop() -> (World, ActorId) {
+        let mut world = World::new();
+        let shop = Shop::new(vec![(ItemKind::Bread, 3)]);
+        let shopkeeper_id = world.actors.add(
+            Actor::new(ActorKind::Shopkeeper, (0, 0), 4).with_shop(shop)
+        );
+        world.restock_shops();
+        (world, shopkeeper_id)
+    }
+
+    #[test]
+    fn buy_and_sell() {
+        let (mut world, shopkeeper_id) = world_with_shop();
+        let player_id = world.player_id();
+        world.actors.get_mut(&player_id).unwrap().coins = 10;
+        let bread = world.actors.get(&shopkeeper_id).unwrap().inventory[0];
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+
+        world.buy(&player_id, &shopkeeper_id, &bread);
+        assert_eq!(world.actors.get(&player_id).unwrap().coins, 7);
+        assert_eq!(world.actors.get(&shopkeeper_id).unwrap().coins, 3);
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 2);
+
+        let own_bread = world.actors.get(&player_id).unwrap().inventory[0];
+        world.sell(&player_id, &shopkeeper_id, &own_bread);
+        assert_eq!(world.actors.get(&player_id).unwrap().coins, 8);
+        assert!(world.actors.get(&player_id).unwrap().inventory.is_empty());
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+    }
+
+    #[test]
+    fn buy_refused_without_coins() {
+        let (mut world, shopkeeper_id) = world_with_shop();
+        let player_id = world.player_id();
+        let bread = world.actors.get(&shopkeeper_id).unwrap().inventory[0];
+
+        world.buy(&player_id, &shopkeeper_id, &bread);
+        assert!(world.actors.get(&player_id).unwrap().inventory.is_empty());
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+    }
+
+    #[test]
+    fn no_trade_outside_opening_hours() {
+        let (mut world, shopkeeper_id) = world_with_shop();
+        let player_id = world.player_id();
+        world.actors.get_mut(&player_id).unwrap().coins = 10;
+        let shop = world.actors.get_mut(&shopkeeper_id).unwrap().shop.take().unwrap();
+        world.actors.get_mut(&shopkeeper_id).unwrap().shop = Some(shop.with_opening_hours(8, 18));
+        let bread = world.actors.get(&shopkeeper_id).unwrap().inventory[0];
+
+        // at midnight the shop is closed
+        world.buy(&player_id, &shopkeeper_id, &bread);
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+
+        world.time = 9 * crate::schedule::TURNS_PER_HOUR;
+        world.buy(&player_id, &shopkeeper_id, &bread);
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 2);
+    }
+
+    #[test]
+    fn no_trade_with_hostile_shopkeeper() {
+        let (mut world, shopkeeper_id) = world_with_shop();
+        let player_id = world.player_id();
+        world.actors.get_mut(&player_id).unwrap().coins = 10;
+        let bread = world.actors.get(&shopkeeper_id).unwrap().inventory[0];
+
+        world.factions.record_deed(Deed::Murder, Faction::Villagers);
+        world.buy(&player_id, &shopkeeper_id, &bread);
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+    }
+
+    #[test]
+    fn restock_after_interval() {
+        let (mut world, shopkeeper_id) = world_with_shop();
+        let bread = world.actors.get(&shopkeeper_id).unwrap().inventory[0];
+        world.take_one(&bread);
+
+        world.time += 1;
+        world.restock_shops();
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 2);
+
+        world.time += 100;
+        world.restock_shops();
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 3);
+    }
+
+    #[test]
+    fn bump_into_closed_door() {
+        let (world, door) = world_with_door(DoorState::Closed);
+        let action = move_by(&world, &world.player_id(), 1, 0, false);
+        assert!(matches!(action, Some(Action::OpenDoor { pos, .. }) if pos == door));
+    }
+
+    fn world_with_quest() -> World {
+        let mut world = World::new();
+        let quest = Quest::parse("
+quest ore
+title Ore
+stage Find some ore.
+objective fetch ore
+stage Deliver it.
+objective flag ore_delivered
+reward coins 20
+").unwrap().remove(0);
+        world.quests.insert(quest.id.clone(), quest);
+        world
+    }
+
+    #[test]
+    fn complete_quest() {
+        let mut world = world_with_quest();
+        let player_id = world.player_id();
+        world.start_quest("ore");
+        assert_eq!(world.quest_status("ore"), Some(QuestStatus::Active));
+
+        let ore = world.items.add(Item::new(ItemKind::Ore).with_pos(world.player_pos()));
+        world.pick_up(&player_id, &ore);
+        world.dispatch_events();
+        assert_eq!(world.journal[0].stage, 1);
+
+        world.set_flag("ore_delivered");
+        world.dispatch_events();
+        assert_eq!(world.quest_status("ore"), Some(QuestStatus::Completed));
+        assert_eq!(world.actors.get(&player_id).unwrap().coins, 20);
+    }
+
+    #[test]
+    fn quest_stage_already_fulfilled() {
+        let mut world = world_with_quest();
+        let player_id = world.player_id();
+        world.give_item(&player_id, Item::new(ItemKind::Ore));
+
+        world.start_quest("ore");
+        assert_eq!(world.journal[0].stage, 1);
+    }
+
+    #[test]
+    fn subscribers_receive_events() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let (mut world, door) = world_with_door(DoorState::Closed);
+        let player_id = world.player_id();
+        let received = Rc::new(RefCell::new(vec![]));
+        let log = received.clone();
+        world.events.subscribe(move |event| log.borrow_mut().push(event.clone()));
+
+        world.open_door(&player_id, &door);
+        world.move_actor(&player_id, door);
+        world.end_turn();
+        assert!(received.borrow().is_empty());
+
+        let from = door - Point::from((1, 0));
+        world.dispatch_events();
+        assert_eq!(*received.borrow(), vec![
+            GameEvent::DoorOpened { actor_id: player_id, pos: door },
+            GameEvent::ActorMoved { actor_id: player_id, from, to: door },
+            GameEvent::TurnEnded { time: 1 }
+        ]);
+    }
+
+    fn world_with_dog() -> (World, ActorId) {
+        let mut world = World::new();
+        let pos = world.player_pos() + Point::from((1, 0));
+        let dog_id = world.actors.add(Actor::new(ActorKind::Dog, pos, 4));
+        (world, dog_id)
+    }
+
+    #[test]
+    fn feeding_bread_tames_animal() {
+        let (mut world, dog_id) = world_with_dog();
+        let player_id = world.player_id();
+        let bread = world.give_item(&player_id, Item::new(ItemKind::Bread)).unwrap();
+        let dog_pos = world.actors.get(&dog_id).unwrap().pos;
+
+        world.use_item_on(&bread, &player_id, &dog_pos);
+        assert!(world.is_companion_of(&dog_id, &player_id));
+        assert!(world.actors.get(&dog_id).unwrap().is_following());
+        assert!(world.items.get(&bread).is_none());
+
+        world.command_companion(&dog_id, CompanionCommand::Stay);
+        assert!(!world.actors.get(&dog_id).unwrap().is_following());
+    }
+
+    #[test]
+    fn using_a_potion_identifies_it() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let kind = ItemKind::Potion(Potion::Healing);
+        let potion = world.give_item(&player_id, Item::new(kind.clone()).with_quantity(2)).unwrap();
+        assert!(!world.known_items.is_known(&kind));
+        assert!(!world.describe_item(world.items.get(&potion).unwrap()).contains("healing"));
+
+        world.use_item(&potion, &player_id);
+        assert!(world.known_items.is_known(&kind));
+        assert_eq!(world.describe_item(world.items.get(&potion).unwrap()), "a potion of healing");
+    }
+
+    #[test]
+    fn identify_without_charges() {
+        let mut world = World::new();
+        world.identify(&ItemKind::Wand(crate::spell::Spell::Bolt));
+        assert!(world.messages.iter().any(|message| message.text == "You identified a wand of magic bolt."));
+    }
+
+    #[test]
+    fn buying_does_not_identify() {
+        let (mut world, shopkeeper_id) = world_with_shop();
+        let player_id = world.player_id();
+        world.actors.get_mut(&player_id).unwrap().coins = 20;
+        let potion = world.give_item(&shopkeeper_id, Item::new(ItemKind::Potion(Potion::Healing))).unwrap();
+
+        world.buy(&player_id, &shopkeeper_id, &potion);
+        world.dispatch_events();
+        assert!(world.messages.iter().any(|message| message.text.starts_with("You buy")));
+        assert!(world.messages.iter().all(|message| !message.text.contains("healing")));
+    }
+
+    #[test]
+    fn craft_at_the_anvil() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        world.recipes = crate::craft::Recipe::parse(
+            "recipe sword\ninput ore 3\ntool hammer\nfeature anvil\noutput sword"
+        ).unwrap();
+        world.give_item(&player_id, Item::new(ItemKind::Ore).with_quantity(4));
+        world.give_item(&player_id, Item::new(ItemKind::Hammer));
+        assert!(!world.can_craft(&player_id, &world.recipes[0]));
+
+        let anvil = world.player_pos() + Point::from((1, 1));
+        world.terrain.insert(anvil, Terrain::from(TerrainKind::Grass).with(TerrainFeature::Anvil));
+        world.craft(&player_id, "sword");
+        assert_eq!(world.count_items(&player_id, &ItemKind::Ore), 1);
+        assert_eq!(world.count_items(&player_id, &ItemKind::Hammer), 1);
+        assert_eq!(world.count_items(&player_id, &ItemKind::Sword), 1);
+
+        // not enough ore left
+        assert!(!world.can_craft(&player_id, &world.recipes[0]));
+    }
+
+    #[test]
+    fn eating_bread_restores_nutrition() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let bread = world.give_item(&player_id, Item::new(ItemKind::Bread).with_quantity(2)).unwrap();
+
+        // too full to eat at the beginning
+        world.use_item(&bread, &player_id);
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 2);
+
+        world.actors.get_mut(&player_id).unwrap().nutrition.value = 10;
+        world.use_item(&bread, &player_id);
+        assert_eq!(world.items.get(&bread).unwrap().quantity, 1);
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.nutrition.value, 10 + MAX_NUTRITION / 3);
+        assert_eq!(player.hunger(), Hunger::NotHungry);
+    }
+
+    #[test]
+    fn starvation_costs_health() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let health = world.actors.get(&player_id).unwrap().health.value;
+        world.actors.get_mut(&player_id).unwrap().nutrition.value = 1;
+
+        world.end_turn();
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.hunger(), Hunger::Starving);
+        assert_eq!(world.attack_value(&player_id), BASE_ATTACK - 2);
+
+        for _ in 0..STARVATION_INTERVAL {
+            world.end_turn();
+        }
+        assert_eq!(world.actors.get(&player_id).unwrap().health.value, health - 1);
+    }
+
+    #[test]
+    fn swap_places_with_companion() {
+        let (mut world, dog_id) = world_with_dog();
+        let player_id = world.player_id();
+        let player_pos = world.player_pos();
+        let dog_pos = world.actors.get(&dog_id).unwrap().pos;
+        world.terrain.insert(dog_pos, Terrain::from(TerrainKind::Grass));
+
+        // a stranger's dog blocks the way
+        assert!(move_by(&world, &player_id, 1, 0, false).is_none());
+
+        world.tame(&dog_id, &player_id);
+        let action = move_by(&world, &player_id, 1, 0, false);
+        assert!(matches!(action, Some(Action::Move { pos, .. }) if pos == dog_pos));
+
+        world.move_actor(&player_id, dog_pos);
+        assert_eq!(world.player_pos(), dog_pos);
+        assert_eq!(world.actors.get(&dog_id).unwrap().pos, player_pos);
+    }
+
+    #[test]
+    fn killing_grants_experience() {
+        let (mut world, dog_id) = world_with_dog();
+        let player_id = world.player_id();
+        let health = world.actors.get(&player_id).unwrap().health.max;
+        world.gain_experience(&player_id, 18);
+
+        // a dog is worth 5 points, 20 points are needed for level 2
+        world.inflict_damage(&player_id, &dog_id, 100);
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.experience.level, 2);
+        assert_eq!(player.experience.points, 3);
+        assert_eq!(player.health.max, health + experience::HEALTH_PER_LEVEL);
+    }
+
+    #[test]
+    fn practice_improves_skill() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let chance = world.actors.get(&player_id).unwrap().failure_chance(&SkillKind::Swim);
+
+        for _ in 0..10 {
+            world.use_skill(&player_id, &SkillKind::Swim);
+        }
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.skill_rank(&SkillKind::Swim), 1);
+        assert!(player.failure_chance(&SkillKind::Swim) < chance);
+        assert!(world.messages.iter().any(|message| message.text.contains("swimming skill improves")));
+    }
+
+    #[test]
+    fn climb_over_hedge() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let hedge = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(hedge, Terrain::from(TerrainKind::Hedge));
+        assert!(move_by(&world, &player_id, 1, 0, false).is_none());
+
+        // a well practiced climber never falls
+        let player = world.actors.get_mut(&player_id).unwrap();
+        player.skills.push(Skill::new(SkillKind::Climb));
+        player.practice.insert(SkillKind::Climb, 1000);
+        assert!(matches!(move_by(&world, &player_id, 1, 0, false), Some(Action::Move { .. })));
+        world.move_actor(&player_id, hedge);
+        assert_eq!(world.player_pos(), hedge);
+    }
+
+    #[test]
+    fn drowning_in_deep_water() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let water = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(water, Terrain::from(TerrainKind::Water));
+        let bread = world.items.add(Item::new(ItemKind::Bread).with_owner(player_id));
+        let player = world.actors.get_mut(&player_id).unwrap();
+        player.inventory.push(bread);
+        player.skills.push(Skill::new(SkillKind::Swim));
+        player.practice.insert(SkillKind::Swim, 1000);
+        let health = player.health.value;
+
+        world.move_actor(&player_id, water);
+        assert_eq!(world.player_pos(), water);
+        assert!(world.items.get(&bread).is_none());
+
+        for _ in 0..MAX_BREATH {
+            world.end_turn();
+        }
+        let player = world.actors.get(&player_id).unwrap();
+        assert_eq!(player.breath.value, 0);
+        assert_eq!(player.health.value, health - DROWNING_DAMAGE);
+    }
+
+    #[test]
+    fn dogs_swim_without_drowning() {
+        let (mut world, dog_id) = world_with_dog();
+        let dog_pos = world.actors.get(&dog_id).unwrap().pos;
+        let water = dog_pos + Point::from((0, 1));
+        world.terrain.insert(water, Terrain::from(TerrainKind::Water));
+        assert!(world.can_enter(&dog_id, &water));
+        assert!(!world.can_enter(&world.player_id(), &water));
+
+        world.actors.get_mut(&dog_id).unwrap().pos = water;
+        for _ in 0..2 * MAX_BREATH {
+            world.end_turn();
+        }
+        let dog = world.actors.get(&dog_id).unwrap();
+        assert_eq!(dog.health.value, dog.health.max);
+    }
+
+    #[test]
+    fn darkness_limits_fov() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let near = world.player_pos() + Point::from((1, 1));
+        let far = world.player_pos() + Point::from((3, 0));
+
+        // midnight
+        world.time = 0;
+        world.update_lights();
+        world.update_fov(&player_id);
+        assert!(world.fov[&player_id].contains(&near));
+        assert!(!world.fov[&player_id].contains(&far));
+
+        let torch = world.items.add(Item::new(ItemKind::Torch).with_owner(player_id));
+        world.actors.get_mut(&player_id).unwrap().inventory.push(torch);
+        assert!(world.carries_light(&player_id));
+        world.update_lights();
+        world.update_fov(&player_id);
+        assert!(world.fov[&player_id].contains(&far));
+    }
+
+    #[test]
+    fn rain_fills_empty_bottles_outdoors() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let bottles = world.items.add(Item::new(ItemKind::Potion(Potion::Empty)).with_quantity(2).with_owner(player_id));
+        world.actors.get_mut(&player_id).unwrap().inventory.push(bottles);
+        world.weather = WeatherState { current: Weather::Rain, forecast: Weather::Rain, changes_at: 1000, frozen: false };
+
+        // no rain inside of a house
+        world.terrain.insert(world.player_pos(), Terrain::from(TerrainKind::StoneFloor));
+        world.collect_rain(&player_id);
+        assert_eq!(world.count_items(&player_id, &ItemKind::Potion(Potion::Water)), 0);
+
+        world.terrain.insert(world.player_pos(), Terrain::from(TerrainKind::Grass));
+        for _ in 0..RAIN_FILL_INTERVAL {
+            world.end_turn();
+        }
+        assert_eq!(world.count_items(&player_id, &ItemKind::Potion(Potion::Water)), 1);
+        assert_eq!(world.count_items(&player_id, &ItemKind::Potion(Potion::Empty)), 1);
+    }
+
+    #[test]
+    fn shallow_water_freezes_in_winter() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pond = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(pond, Terrain::from(TerrainKind::ShallowWater));
+        assert!(!world.can_enter(&player_id, &pond));
+
+        world.freeze_water(true);
+        assert_eq!(world.terrain[&pond].kind, TerrainKind::Ice);
+        assert!(world.can_enter(&player_id, &pond));
+
+        world.freeze_water(false);
+        assert_eq!(world.terrain[&pond].kind, TerrainKind::ShallowWater);
+    }
+
+    #[test]
+    fn fog_reduces_fov() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let far = world.player_pos() + Point::from((5, 0));
+        world.update_fov(&player_id);
+        assert!(world.fov[&player_id].contains(&far));
+
+        world.weather.current = Weather::Fog;
+        world.update_fov(&player_id);
+        assert!(!world.fov[&player_id].contains(&far));
+    }
+
+    #[test]
+    fn snare_holds_the_player() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let snare = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(snare, Terrain::from(TerrainKind::Grass)
+                             .with(TerrainFeature::Trap(Trap::hidden(TrapKind::Snare))));
+
+        world.move_actor(&player_id, snare);
+        assert!(!world.terrain[&snare].has_hidden_feature());
+        for _ in 0..trap::SNARE_TURNS {
+            world.move_actor(&player_id, snare + Point::from((1, 0)));
+            assert_eq!(world.player_pos(), snare);
+        }
+        world.move_actor(&player_id, snare + Point::from((1, 0)));
+        assert_eq!(world.player_pos(), snare + Point::from((1, 0)));
+
+        // walking back into the revealed trap disarms it
+        assert!(matches!(move_by(&world, &player_id, -1, 0, false), Some(Action::Disarm { .. })));
+        let experience = world.actors.get(&player_id).unwrap().experience.clone();
+        world.disarm(&player_id, &snare, 1.0);
+        assert!(world.terrain[&snare].feature.is_none());
+        let mut expected = experience;
+        expected.gain(trap::DISARM_POINTS);
+        assert_eq!(world.actors.get(&player_id).unwrap().experience, expected);
+    }
+
+    #[test]
+    fn searching_reveals_secret_doors() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let wall = world.player_pos() + Point::from((0, 2));
+        world.terrain.insert(wall, Terrain::from(TerrainKind::Wall).with(TerrainFeature::SecretDoor));
+        assert_eq!(feature_index(&world.terrain[&wall]), None);
+
+        world.reveal_hidden(&player_id, trap::SEARCH_RADIUS, 1.0);
+        assert_eq!(world.terrain[&wall].kind, TerrainKind::Door(DoorState::Closed));
+        assert!(world.messages.iter().any(|message| message.text.contains("secret door")));
+    }
+
+    #[test]
+    fn dig_through_wall_with_pickaxe() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let wall = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(wall, Terrain::from(TerrainKind::Wall));
+        let pickaxe = world.items.add(Item::new(ItemKind::Pickaxe).with_owner(player_id));
+        world.actors.get_mut(&player_id).unwrap().inventory.push(pickaxe);
+
+        world.use_item_on(&pickaxe, &player_id, &wall);
+        assert_eq!(world.terrain.get(&wall).unwrap().kind, TerrainKind::StoneFloor);
+        let durability = world.items.get(&pickaxe).unwrap().durability.as_ref().unwrap();
+        assert!(durability.value < durability.max);
+        assert_eq!(world.events.next(), Some(GameEvent::TerrainChanged { actor_id: player_id, pos: wall }));
+    }
+
+    #[test]
+    fn planks_bridge_the_water() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let water = world.player_pos() + Point::from((0, 1));
+        world.terrain.insert(water, Terrain::from(TerrainKind::Water));
+        let planks = world.items.add(Item::new(ItemKind::Planks).with_quantity(2).with_owner(player_id));
+        world.actors.get_mut(&player_id).unwrap().inventory.push(planks);
+        assert!(!world.can_enter(&player_id, &water));
+
+        world.use_item_on(&planks, &player_id, &water);
+        assert_eq!(world.terrain.get(&water).unwrap().kind, TerrainKind::Bridge(Orientation::Vertical));
+        assert_eq!(world.items.get(&planks).unwrap().quantity, 1);
+        assert!(world.can_enter(&player_id, &water));
+    }
+
+    #[test]
+    fn select_locked_container_with_key() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pos = world.player_pos() + Point::from((1, 0));
+        let chest = world.items.add(Item::new(ItemKind::Chest).with_pos(pos).with_lock(2));
+        let key = world.give_item(&player_id, Item::new(ItemKind::Key(2))).unwrap();
+
+        let positions = match world.use_item(&key, &player_id) {
+            InputMode::SelectUse { positions, item_id } if item_id == key => positions,
+            _ => panic!("expected to select a lock")
+        };
+        assert!(positions.contains(&pos));
+        world.use_item_on(&key, &player_id, &pos);
+        assert!(world.open_container(&chest));
+    }
+
+    #[test]
+    fn equip_only_carried_items() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let boots = world.items.add(Item::new(ItemKind::ClimbingBoots).with_pos(world.player_pos()));
+
+        world.equip(&player_id, &boots);
+        let player = world.actors.get(&player_id).unwrap();
+        assert!(player.equipment.items().next().is_none());
+        assert!(!player.has_skill(&SkillKind::Climb));
+    }
+
+    #[test]
+    fn received_items_are_logged() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let bread = world.acquire_item(&player_id, Item::new(ItemKind::Bread), Acquisition::Gift).unwrap();
+        assert_eq!(world.events.next(), Some(GameEvent::ItemReceived {
+            actor_id: player_id, item_id: bread, kind: ItemKind::Bread, quantity: 1, acquisition: Acquisition::Gift
+        }));
+
+        world.acquire_item(&player_id, Item::new(ItemKind::Bread), Acquisition::Craft);
+        world.dispatch_events();
+        assert!(world.messages.iter().any(|message| message.text == "You craft a loaf of bread."));
+    }
+
+    #[test]
+    fn zapping_a_wand_uses_a_charge() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let spell = crate::spell::Spell::Light;
+        let wand = world.give_item(&player_id, Item::new(ItemKind::Wand(spell))).unwrap();
+        assert!(matches!(world.use_item(&wand, &player_id), InputMode::Target { .. }));
+
+        world.zap(&player_id, &wand, world.player_pos() + Point::from((3, 0)));
+        let charges = world.items.get(&wand).unwrap().charges.as_ref().unwrap();
+        assert_eq!(charges.value, spell.charges() - 1);
+        assert!(world.known_items.is_known(&ItemKind::Wand(spell)));
+    }
+
+    #[test]
+    fn cannot_climb_into_unmapped_terrain() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let hedge = world.player_pos();
+        world.terrain.insert(hedge, Terrain::from(TerrainKind::Hedge));
+        let player = world.actors.get_mut(&player_id).unwrap();
+        player.skills.push(Skill::new(SkillKind::Climb));
+        player.practice.insert(SkillKind::Climb, 1000);
+
+        assert!(!world.can_enter(&player_id, &(hedge + Point::from((1, 0)))));
+        assert!(move_by(&world, &player_id, 1, 0, false).is_none());
+    }
+
+    #[test]
+    fn cut_border_hedge_leads_nowhere() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let hedge = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(world.player_pos(), Terrain::from(TerrainKind::Grass));
+        world.terrain.insert(hedge, Terrain::from(TerrainKind::Hedge));
+        let axe = world.give_item(&player_id, Item::new(ItemKind::Axe)).unwrap();
+
+        world.use_item_on(&axe, &player_id, &hedge);
+        assert!(world.can_enter(&player_id, &hedge));
+        assert!(!world.can_enter(&player_id, &(hedge + Point::from((1, 0)))));
+    }
+
+    #[test]
+    fn closed_door_stops_the_light() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        world.time = 0;
+        let door = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(door, Terrain::from(TerrainKind::Door(DoorState::Open)));
+        world.give_item(&player_id, Item::new(ItemKind::Torch));
+        world.update_lights();
+        let behind = door + Point::from((1, 0));
+        assert!(world.light.is_visible(&behind));
+
+        world.close_door(&player_id, &door);
+        assert!(!world.light.is_visible(&behind));
+        world.open_door(&player_id, &door);
+        assert!(world.light.is_visible(&behind));
+    }
+
+    #[test]
+    fn wand_without_charges_cannot_be_aimed() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let mut wand = Item::new(ItemKind::Wand(crate::spell::Spell::Bolt));
+        wand.charges = None;
+        let wand = world.give_item(&player_id, wand).unwrap();
+        assert!(matches!(world.use_item(&wand, &player_id), InputMode::Default));
+
+        let empty = world.give_item(&player_id, Item::new(ItemKind::Wand(crate::spell::Spell::Light)).with_charges(0)).unwrap();
+        assert!(matches!(world.use_item(&empty, &player_id), InputMode::Default));
+    }
+
+    #[test]
+    fn failed_disarm_sets_the_trap_off() {
+        let mut world = World::new();
+        let player_id = world.player_id();
+        let pit = world.player_pos() + Point::from((1, 0));
+        world.terrain.insert(pit, Terrain::from(TerrainKind::Grass)
+                             .with(TerrainFeature::Trap(Trap { kind: TrapKind::Pit, hidden: false, owner: None })));
+        let health = world.actors.get(&player_id).unwrap().health.value;
+
+        world.disarm(&player_id, &pit, 0.0);
+        assert!(world.terrain[&pit].feature.is_some());
+        assert_eq!(world.actors.get(&player_id).unwrap().health.value, health - trap::PIT_DAMAGE);
+    }
+
+    #[test]
+    fn traps_catch_strangers_but_not_their_owners() {
+        let mut world = World::new();
+        let pos = world.player_pos() + Point::from((3, 0));
+        let pit = pos + Point::from((1, 0));
+        world.terrain.insert(pit, Terrain::from(TerrainKind::Grass)
+                             .with(TerrainFeature::Trap(Trap::hidden(TrapKind::Pit).with_owner(Faction::Villagers))));
+        let villager_id = world.actors.add(Actor::new(ActorKind::Townsfolk, pos, 5).with_faction(Faction::Villagers));
+        let bandit_id = world.actors.add(Actor::new(ActorKind::Townsfolk, pos, 5).with_faction(Faction::Bandits));
+
+        world.move_actor(&villager_id, pit);
+        assert_eq!(world.actors.get(&villager_id).unwrap().health.value, 5);
+
+        world.move_actor(&bandit_id, pit);
+        assert_eq!(world.actors.get(&bandit_id).unwrap().health.value, 5 - trap::PIT_DAMAGE);
+    }
 }